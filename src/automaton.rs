@@ -0,0 +1,274 @@
+use std::hash::Hash;
+use std::str::FromStr;
+
+use crate::cycle::{self, Cycle};
+use crate::grid::Grid;
+use crate::neighborhood::{Boundary, Neighborhood};
+
+/// Birth and survival rule of a two-state cellular automaton: a dead tile
+/// comes alive if its number of live neighbors is one of the birth counts, and
+/// a live tile stays alive if its number is one of the survival counts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    birth: Vec<bool>,
+    survival: Vec<bool>,
+}
+
+impl Rule {
+    /// Creates rule from counts of live neighbors leading to birth and
+    /// survival.
+    pub fn new(
+        birth: impl IntoIterator<Item = usize>,
+        survival: impl IntoIterator<Item = usize>,
+    ) -> Self {
+        let to_mask = |counts: &mut dyn Iterator<Item = usize>| {
+            let mut mask = Vec::new();
+            for n in counts {
+                if n >= mask.len() {
+                    mask.resize(n + 1, false);
+                }
+                mask[n] = true;
+            }
+            mask
+        };
+        Self {
+            birth: to_mask(&mut birth.into_iter()),
+            survival: to_mask(&mut survival.into_iter()),
+        }
+    }
+
+    /// Returns rule under which live tiles having fewer than `threshold` live
+    /// tiles among their `nb_neighbors` neighbors die, and no tile is born.
+    pub fn removal(threshold: usize, nb_neighbors: usize) -> Self {
+        Self::new([], threshold..=nb_neighbors)
+    }
+
+    /// Returns true if a dead tile with `n` live neighbors comes alive.
+    pub fn is_born(&self, n: usize) -> bool {
+        self.birth.get(n).copied().unwrap_or(false)
+    }
+
+    /// Returns true if a live tile with `n` live neighbors stays alive.
+    pub fn survives(&self, n: usize) -> bool {
+        self.survival.get(n).copied().unwrap_or(false)
+    }
+}
+
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    /// Parses `B<digits>/S<digits>`, such as `B3/S23` for Conway's game of
+    /// life.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let counts = |part: &str, prefix: char| -> anyhow::Result<Vec<usize>> {
+            let digits = part
+                .strip_prefix(prefix)
+                .or_else(|| part.strip_prefix(prefix.to_ascii_lowercase()))
+                .ok_or_else(|| anyhow::anyhow!("invalid rule: {}: expected {}", s, prefix))?;
+            digits
+                .chars()
+                .map(|c| {
+                    c.to_digit(10)
+                        .map(|d| d as usize)
+                        .ok_or_else(|| anyhow::anyhow!("invalid rule: {}: unexpected {:?}", s, c))
+                })
+                .collect()
+        };
+        let (birth, survival) = s
+            .split_once('/')
+            .ok_or_else(|| anyhow::anyhow!("invalid rule: {} (expected B<counts>/S<counts>)", s))?;
+        Ok(Self::new(counts(birth, 'B')?, counts(survival, 'S')?))
+    }
+}
+
+/// A two-state cellular automaton on grids of `T`, where tiles are alive if
+/// equal to `live` and dead if equal to `dead`.  Other tiles never change and
+/// count as dead.
+#[derive(Debug, Clone)]
+pub struct Automaton<T> {
+    rule: Rule,
+    neighborhood: Neighborhood,
+    boundary: Boundary<T>,
+    live: T,
+    dead: T,
+}
+
+impl<T: Clone + PartialEq> Automaton<T> {
+    pub fn new(
+        rule: Rule,
+        neighborhood: Neighborhood,
+        boundary: Boundary<T>,
+        live: T,
+        dead: T,
+    ) -> Self {
+        Self {
+            rule,
+            neighborhood,
+            boundary,
+            live,
+            dead,
+        }
+    }
+
+    /// Returns next generation of `grid`, all tiles being updated at once.
+    pub fn step(&self, grid: &Grid<T>) -> Grid<T> {
+        let mut next = grid.clone();
+        for (x, y) in grid.positions() {
+            let tile = &grid[(x, y)];
+            let is_live = *tile == self.live;
+            if !is_live && *tile != self.dead {
+                continue;
+            }
+            let nb_live = grid
+                .neighborhood(x, y, &self.neighborhood, &self.boundary)
+                .filter(|&t| *t == self.live)
+                .count();
+            if is_live && !self.rule.survives(nb_live) {
+                next[(x, y)] = self.dead.clone();
+            } else if !is_live && self.rule.is_born(nb_live) {
+                next[(x, y)] = self.live.clone();
+            }
+        }
+        next
+    }
+
+    /// Replaces `grid` with its generation `generation` if given, or else
+    /// with first generation equal to an earlier one.  Returns cycle if
+    /// generations repeated along the way.
+    pub fn run(&self, grid: &mut Grid<T>, generation: Option<usize>) -> Option<Cycle>
+    where
+        T: Eq + Hash,
+    {
+        let step = |grid: &Grid<T>| self.step(grid);
+        let iteration = match generation {
+            Some(generation) => cycle::fast_forward(grid.clone(), step, generation),
+            None => cycle::iterate(grid.clone(), step, None),
+        };
+        *grid = iteration.state;
+        iteration.cycle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Tile;
+    use indoc::indoc;
+    use std::io::Cursor;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum Cell {
+        Live,
+        Dead,
+        Wall,
+    }
+
+    impl Tile for Cell {
+        fn from_char(c: char) -> Option<Self> {
+            match c {
+                '#' => Some(Cell::Live),
+                '.' => Some(Cell::Dead),
+                'X' => Some(Cell::Wall),
+                _ => None,
+            }
+        }
+
+        fn to_char(&self) -> char {
+            match self {
+                Cell::Live => '#',
+                Cell::Dead => '.',
+                Cell::Wall => 'X',
+            }
+        }
+    }
+
+    fn life() -> Automaton<Cell> {
+        Automaton::new(
+            "B3/S23".parse().unwrap(),
+            Neighborhood::moore(),
+            Boundary::Clip,
+            Cell::Live,
+            Cell::Dead,
+        )
+    }
+
+    fn grid(s: &[u8]) -> Grid<Cell> {
+        Grid::load(Cursor::new(s)).unwrap()
+    }
+
+    #[test]
+    fn parse_rule() {
+        let rule: Rule = "B36/s23".parse().unwrap();
+        assert!(rule.is_born(3) && rule.is_born(6) && !rule.is_born(2));
+        assert!(rule.survives(2) && !rule.survives(4) && !rule.survives(100));
+        assert_eq!("B/S".parse::<Rule>().unwrap(), Rule::new([], []));
+        assert!("B3S23".parse::<Rule>().is_err());
+        assert!("S23/B3".parse::<Rule>().is_err());
+        assert!("B3/S2x".parse::<Rule>().is_err());
+        assert_eq!("B/S45678".parse::<Rule>().unwrap(), Rule::removal(4, 8));
+    }
+
+    #[test]
+    fn blinker() {
+        let mut blinker = grid(indoc! {b"
+            .....
+            ..#..
+            ..#..
+            ..#..
+            .....
+        "});
+        let initial = blinker.clone();
+        assert_eq!(
+            life().step(&blinker),
+            grid(indoc! {b"
+                .....
+                .....
+                .###.
+                .....
+                .....
+            "})
+        );
+        assert_eq!(
+            life().run(&mut blinker, None),
+            Some(Cycle {
+                start: 0,
+                period: 2
+            })
+        );
+        assert_eq!(blinker, initial);
+        assert_eq!(life().run(&mut blinker, Some(1)), None);
+        assert_ne!(blinker, initial);
+        let cycle = life().run(&mut blinker, Some(1_000_000_001));
+        assert_eq!(cycle.map(|c| c.period), Some(2));
+        assert_eq!(blinker, initial);
+    }
+
+    #[test]
+    fn fixed_point() {
+        // Block is stable once lone tile dies.
+        let mut block = grid(indoc! {b"
+            ##...
+            ##..#
+        "});
+        let cycle = life().run(&mut block, None).unwrap();
+        assert!(cycle.is_fixed_point());
+        assert_eq!(cycle.start, 1);
+        assert_eq!(block.to_string(), "##...\n##...\n");
+    }
+
+    #[test]
+    fn other_tiles() {
+        // Walls neither change nor count as live.
+        let mut walls = grid(b"X#X\n#.#\nXXX\n");
+        let next = life().step(&walls);
+        assert_eq!(next.to_string(), "X#X\n.#.\nXXX\n");
+        let cycle = life().run(&mut walls, None);
+        assert_eq!(
+            cycle,
+            Some(Cycle {
+                start: 2,
+                period: 1
+            })
+        );
+    }
+}
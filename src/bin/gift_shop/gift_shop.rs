@@ -1,20 +1,86 @@
 // Day 2 - The Gift Shop
 
-use std::io::{BufRead, Split};
+use std::io::{BufRead, Split, Write};
+
+use advent_code_25::CommandLine;
+use advent_code_25::checkpoint::{self, Checkpoint, Checkpointer, Fingerprint};
+
+/// Number of IDs scanned between two checkpoints within a range.
+const IDS_PER_CHECKPOINT: usize = 1 << 16;
 
 fn main() -> anyhow::Result<()> {
-    let input = advent_code_25::open_input_file()?;
-    let answer = solve_for(input)?;
+    let cmd_line = CommandLine::from_env();
+    let checkpointer = Checkpointer::from_command_line(&cmd_line)?;
+    let input = cmd_line.open_input_file()?;
+    let answer = match checkpointer {
+        Some(mut checkpointer) => {
+            let fingerprint = Fingerprint::of_input(cmd_line.open_input_file()?)?;
+            let progress = checkpointer.start(fingerprint, || Ok(Progress::default()))?;
+            let answer = solve_for(input, progress, |progress| {
+                checkpointer.maybe_save(progress).map(drop)
+            })?;
+            checkpointer.finish()?;
+            answer
+        }
+        None => solve_for(input, Progress::default(), |_| Ok(()))?,
+    };
     println!("answer: {}", answer);
     Ok(())
 }
 
-fn solve_for<R: BufRead>(input: R) -> anyhow::Result<usize> {
-    let mut acc = 0;
-    for range in ranges_from_input(input)? {
-        acc += sum_invalid_ids_in_range(range?);
+/// How far the scan of ID ranges went.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Progress {
+    /// Number of ranges already scanned.
+    nb_ranges: usize,
+    /// First ID left to scan in next range, or 0 to scan all of it.
+    next_id: usize,
+    /// Sum of invalid IDs in these ranges.
+    sum: usize,
+}
+
+impl Checkpoint for Progress {
+    fn save<W: Write>(&self, mut output: W) -> anyhow::Result<()> {
+        checkpoint::write_field(&mut output, "ranges", self.nb_ranges)?;
+        checkpoint::write_field(&mut output, "id", self.next_id)?;
+        checkpoint::write_field(&mut output, "sum", self.sum)
+    }
+
+    fn restore<R: BufRead>(mut input: R) -> anyhow::Result<Self> {
+        Ok(Self {
+            nb_ranges: checkpoint::read_field(&mut input, "ranges")?,
+            next_id: checkpoint::read_field(&mut input, "id")?,
+            sum: checkpoint::read_field(&mut input, "sum")?,
+        })
     }
-    Ok(acc)
+}
+
+/// Returns sum of invalid IDs, skipping ranges and IDs already scanned
+/// according to `progress`.  Calls `checkpoint` after each range, and every
+/// `IDS_PER_CHECKPOINT` IDs within a range.
+fn solve_for<R: BufRead>(
+    input: R,
+    mut progress: Progress,
+    mut checkpoint: impl FnMut(&Progress) -> anyhow::Result<()>,
+) -> anyhow::Result<usize> {
+    for range in ranges_from_input(input)?.skip(progress.nb_ranges) {
+        let Range(first, last) = range?;
+        let mut start = first.max(progress.next_id);
+        while start <= last {
+            let end = last.min(start.saturating_add(IDS_PER_CHECKPOINT - 1));
+            progress.sum += sum_invalid_ids_in_range(Range(start, end));
+            if end == last {
+                break;
+            }
+            start = end + 1;
+            progress.next_id = start;
+            checkpoint(&progress)?;
+        }
+        progress.next_id = 0;
+        progress.nb_ranges += 1;
+        checkpoint(&progress)?;
+    }
+    Ok(progress.sum)
 }
 
 /// Returns iterator that parses `input` into a sequence of ID ranges.
@@ -126,22 +192,23 @@ fn nb_digits(n: usize) -> u32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     use std::io::Cursor;
 
     #[test]
     fn test_parse_id() {
         assert!(matches!(parse_id(b"1"), Ok(1)));
         assert!(matches!(parse_id(b"123"), Ok(123)));
-        assert!(matches!(parse_id(b"123!"), Err(_)));
+        assert!(parse_id(b"123!").is_err());
     }
 
     #[test]
     fn test_parse_id_range() {
         assert!(matches!(parse_id_range(b"1-2"), Ok(Range(1, 2))));
         assert!(matches!(parse_id_range(b"12-99"), Ok(Range(12, 99))));
-        assert!(matches!(parse_id_range(b"12"), Err(_)));
-        assert!(matches!(parse_id_range(b"12-"), Err(_)));
-        assert!(matches!(parse_id_range(b"-12"), Err(_)));
+        assert!(parse_id_range(b"12").is_err());
+        assert!(parse_id_range(b"12-").is_err());
+        assert!(parse_id_range(b"-12").is_err());
     }
 
     #[test]
@@ -157,6 +224,58 @@ mod tests {
         ranges_from_input(reader)?.collect::<anyhow::Result<Vec<Range>>>()
     }
 
+    #[test]
+    fn resume() -> anyhow::Result<()> {
+        let input = b"11-22,95-115,998-1012";
+        let mut checkpoints = Vec::new();
+        let answer = solve_for(Cursor::new(input), Progress::default(), |progress| {
+            checkpoints.push(*progress);
+            Ok(())
+        })?;
+        assert_eq!(answer, 33 + 210 + 2009);
+        assert_eq!(
+            checkpoints[1],
+            Progress {
+                nb_ranges: 2,
+                next_id: 0,
+                sum: 243
+            }
+        );
+
+        let mut saved = Vec::new();
+        checkpoints[1].save(&mut saved)?;
+        let progress = Progress::restore(Cursor::new(saved))?;
+        let resumed = solve_for(Cursor::new(input), progress, |_| Ok(()))?;
+        assert_eq!(resumed, answer);
+        Ok(())
+    }
+
+    #[test]
+    fn resume_within_range() -> anyhow::Result<()> {
+        let input = b"1-200000,998-1012";
+        let mut checkpoints = Vec::new();
+        let answer = solve_for(Cursor::new(input), Progress::default(), |progress| {
+            checkpoints.push(*progress);
+            Ok(())
+        })?;
+        let expected = sum_invalid_ids_in_range(Range(1, 200000)) + 2009;
+        assert_eq!(answer, expected);
+        // 3 checkpoints within first range, then one after each range.
+        assert_eq!(checkpoints.len(), 5);
+        assert_eq!(checkpoints[0].nb_ranges, 0);
+        assert_eq!(checkpoints[0].next_id, 1 + IDS_PER_CHECKPOINT);
+        assert_eq!(checkpoints[3].next_id, 0);
+
+        for progress in &checkpoints[..3] {
+            let mut saved = Vec::new();
+            progress.save(&mut saved)?;
+            let progress = Progress::restore(Cursor::new(saved))?;
+            let resumed = solve_for(Cursor::new(input), progress, |_| Ok(()))?;
+            assert_eq!(resumed, answer);
+        }
+        Ok(())
+    }
+
     #[test]
     fn digits() {
         assert_eq!(nb_digits(0), 1);
@@ -187,14 +306,37 @@ mod tests {
 
     #[test]
     fn test_is_id_made_of_repeating_sequences() {
-        assert_eq!(is_id_made_of_repeating_sequences(11, 1), true);
-        assert_eq!(is_id_made_of_repeating_sequences(111111, 1), true);
-        assert_eq!(is_id_made_of_repeating_sequences(12, 1), false);
-        assert_eq!(is_id_made_of_repeating_sequences(1212, 2), true);
-        assert_eq!(is_id_made_of_repeating_sequences(121212, 2), true);
-        assert_eq!(is_id_made_of_repeating_sequences(1213, 2), false);
-        assert_eq!(is_id_made_of_repeating_sequences(123123, 3), true);
-        assert_eq!(is_id_made_of_repeating_sequences(123123123, 3), true);
-        assert_eq!(is_id_made_of_repeating_sequences(132123, 3), false);
+        assert!(is_id_made_of_repeating_sequences(11, 1));
+        assert!(is_id_made_of_repeating_sequences(111111, 1));
+        assert!(!is_id_made_of_repeating_sequences(12, 1));
+        assert!(is_id_made_of_repeating_sequences(1212, 2));
+        assert!(is_id_made_of_repeating_sequences(121212, 2));
+        assert!(!is_id_made_of_repeating_sequences(1213, 2));
+        assert!(is_id_made_of_repeating_sequences(123123, 3));
+        assert!(is_id_made_of_repeating_sequences(123123123, 3));
+        assert!(!is_id_made_of_repeating_sequences(132123, 3));
+    }
+
+    /// Returns true if decimal representation of `id` is a sequence of digits
+    /// repeated at least twice.
+    fn is_repetition(id: usize) -> bool {
+        let digits = id.to_string();
+        (1..=digits.len() / 2).any(|len| {
+            digits.len().is_multiple_of(len) && digits[..len].repeat(digits.len() / len) == digits
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn invalid_ids_are_repetitions(id in 0..100_000_000_usize) {
+            prop_assert_eq!(is_invalid_id(id), is_repetition(id));
+        }
+
+        #[test]
+        fn repeated_sequences_are_invalid(sequence in 1..10_000_usize, times in 2..5_usize) {
+            let id: usize = sequence.to_string().repeat(times).parse().unwrap();
+            prop_assert!(is_invalid_id(id));
+            prop_assert!(is_repetition(id));
+        }
     }
 }
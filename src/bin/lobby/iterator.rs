@@ -36,7 +36,7 @@ mod tests {
 
     #[test]
     fn test_first_max_position() {
-        assert!(matches!(b"".iter().first_max_position(), None));
+        assert!(b"".iter().first_max_position().is_none());
         assert_eq!(b"1".iter().first_max_position().unwrap(), 0);
         assert_eq!(b"12".iter().first_max_position().unwrap(), 1);
         assert_eq!(b"21".iter().first_max_position().unwrap(), 0);
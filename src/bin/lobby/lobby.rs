@@ -36,16 +36,14 @@ fn max_joltage_for_bank(bank: &[u8]) -> usize {
 
     // Indices of window in bank where to search for next battery to turn on.
     let mut i_first = 0;
-    let mut i_last = bank.len() - NB_BATTERIES_ON;
 
-    for _ in 0..NB_BATTERIES_ON {
+    for i_last in bank.len() - NB_BATTERIES_ON..bank.len() {
         let i = (bank[i_first..=i_last])
             .iter()
             .first_max_position()
             .unwrap();
         joltage = joltage * 10 + from_digit(bank[i_first + i]);
         i_first += i + 1;
-        i_last += 1;
     }
 
     joltage
@@ -64,6 +62,7 @@ fn from_digit(b: u8) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_is_bank_valid() {
@@ -86,4 +85,26 @@ mod tests {
         assert_eq!(max_joltage_for_bank(b"234234234234278"), 434234234278);
         assert_eq!(max_joltage_for_bank(b"818181911112111"), 888911112111);
     }
+
+    /// Returns largest joltage among all ways of turning on batteries.
+    fn max_joltage_by_brute_force(bank: &[u8]) -> usize {
+        (0_u32..1 << bank.len())
+            .filter(|subset| subset.count_ones() as usize == NB_BATTERIES_ON)
+            .map(|subset| {
+                (0..bank.len())
+                    .filter(|i| subset >> i & 1 == 1)
+                    .fold(0, |joltage, i| joltage * 10 + from_digit(bank[i]))
+            })
+            .max()
+            .unwrap()
+    }
+
+    proptest! {
+        #[test]
+        fn max_joltage_matches_brute_force(
+            bank in prop::collection::vec(b'0'..=b'9', NB_BATTERIES_ON..=16),
+        ) {
+            prop_assert_eq!(max_joltage_for_bank(&bank), max_joltage_by_brute_force(&bank));
+        }
+    }
 }
@@ -0,0 +1,270 @@
+use std::io::BufRead;
+
+use advent_code_25::grid::parse_row;
+
+use crate::grid::RollTile;
+#[cfg(test)]
+use crate::grid::{Grid, RollGridExt};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A grid of rolls storing one bit per tile, 64 tiles of a row per word.
+///
+/// Only the Moore neighborhood with clipped boundaries is supported, which
+/// allows counting neighbors of 64 tiles at once with bitwise operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitGrid {
+    /// Rows of `words_per_row` words, tile `x` being bit `x % 64` of word
+    /// `x / 64`.  Bits past the end of a row are always zero.
+    words: Vec<u64>,
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+}
+
+impl BitGrid {
+    /// Creates grid of given size without any roll.
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(WORD_BITS);
+        Self {
+            words: vec![0; words_per_row * height],
+            width,
+            height,
+            words_per_row,
+        }
+    }
+
+    /// Loads grid in same format as `Grid::load`, without ever storing more
+    /// than one line of text.
+    pub fn load<R: BufRead>(mut input: R) -> anyhow::Result<Self> {
+        let mut grid = Self::new(0, 0);
+        let mut width = None;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if input.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            let line_number = grid.height + 1;
+            let row: Vec<RollTile> = parse_row(&line, line_number)?;
+            let nb_tiles = row.len();
+            let width = *width.get_or_insert_with(|| {
+                grid = Self::new(nb_tiles, 0);
+                nb_tiles
+            });
+            if nb_tiles != width {
+                anyhow::bail!(
+                    "line {}: expected {} tiles, found {}",
+                    line_number,
+                    width,
+                    nb_tiles
+                );
+            }
+            let mut words = vec![0; grid.words_per_row];
+            for (x, tile) in row.into_iter().enumerate() {
+                if tile == RollTile::Roll {
+                    words[x / WORD_BITS] |= 1 << (x % WORD_BITS);
+                }
+            }
+            grid.words.extend(words);
+            grid.height += 1;
+        }
+        Ok(grid)
+    }
+
+    #[cfg(test)]
+    pub fn from_grid(grid: &Grid) -> Self {
+        let mut bits = Self::new(grid.width(), grid.height());
+        for (x, y) in grid.positions() {
+            if grid.is_roll_at(x, y) {
+                bits.words[y * bits.words_per_row + x / WORD_BITS] |= 1 << (x % WORD_BITS);
+            }
+        }
+        bits
+    }
+
+    #[cfg(test)]
+    pub fn to_grid(&self) -> Grid {
+        let mut grid = Grid::new(self.width, self.height, RollTile::Empty);
+        for (x, y) in grid.positions() {
+            if self.is_roll_at(x, y) {
+                grid.set(x, y, RollTile::Roll);
+            }
+        }
+        grid
+    }
+
+    #[cfg(test)]
+    pub fn is_roll_at(&self, x: usize, y: usize) -> bool {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) out of grid",
+            x,
+            y
+        );
+        self.row(y)[x / WORD_BITS] >> (x % WORD_BITS) & 1 == 1
+    }
+
+    /// Returns number of rolls in grid.
+    pub fn nb_rolls(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Removes all rolls having fewer than `threshold` neighboring rolls, all
+    /// decided on the grid as it was before any removal.  Returns number of
+    /// removed rolls.
+    pub fn remove_accessible_rolls(&mut self, threshold: usize) -> usize {
+        let empty = vec![0; self.words_per_row];
+        // Row above current one as it was before removals.
+        let mut above = empty.clone();
+        let mut accessible = vec![0; self.words_per_row];
+        let mut nb_removed = 0;
+        for y in 0..self.height {
+            let below = if y + 1 < self.height {
+                self.row(y + 1)
+            } else {
+                &empty
+            };
+            accessible_rolls(&above, self.row(y), below, threshold, &mut accessible);
+            above.copy_from_slice(self.row(y));
+            let row = self.row_mut(y);
+            for (word, removed) in row.iter_mut().zip(&accessible) {
+                *word &= !removed;
+                nb_removed += removed.count_ones() as usize;
+            }
+        }
+        nb_removed
+    }
+
+    fn row(&self, y: usize) -> &[u64] {
+        &self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    fn row_mut(&mut self, y: usize) -> &mut [u64] {
+        &mut self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+}
+
+/// Sets `accessible` to the rolls of `row` having fewer than `threshold`
+/// neighboring rolls in `above`, `row` and `below`.
+fn accessible_rolls(
+    above: &[u64],
+    row: &[u64],
+    below: &[u64],
+    threshold: usize,
+    accessible: &mut [u64],
+) {
+    for i in 0..row.len() {
+        // Bit-sliced counters: bit `b` of the count of tile `x` is bit `x` of
+        // `count[b]`.  At most 8 neighbors fit in 4 bits.
+        let mut count = [0; 4];
+        for line in [above, below] {
+            add(&mut count, line[i]);
+            add(&mut count, shifted_right(line, i));
+            add(&mut count, shifted_left(line, i));
+        }
+        add(&mut count, shifted_right(row, i));
+        add(&mut count, shifted_left(row, i));
+        accessible[i] = row[i] & less_than(&count, threshold);
+    }
+}
+
+/// Returns word `i` of `line` with each tile replaced by its left neighbor.
+fn shifted_right(line: &[u64], i: usize) -> u64 {
+    let carry = if i > 0 {
+        line[i - 1] >> (WORD_BITS - 1)
+    } else {
+        0
+    };
+    line[i] << 1 | carry
+}
+
+/// Returns word `i` of `line` with each tile replaced by its right neighbor.
+fn shifted_left(line: &[u64], i: usize) -> u64 {
+    let carry = line.get(i + 1).map_or(0, |w| w << (WORD_BITS - 1));
+    line[i] >> 1 | carry
+}
+
+/// Adds one to bit-sliced counters for each bit set in `bits`.
+fn add(count: &mut [u64; 4], bits: u64) {
+    let mut carry = bits;
+    for bit in count {
+        let next = *bit & carry;
+        *bit ^= carry;
+        carry = next;
+    }
+}
+
+/// Returns mask of counters lower than `threshold`.
+fn less_than(count: &[u64; 4], threshold: usize) -> u64 {
+    if threshold >= 1 << count.len() {
+        return !0;
+    }
+    let mut lower = 0;
+    let mut equal = !0;
+    for (b, bit) in count.iter().enumerate().rev() {
+        if threshold >> b & 1 == 1 {
+            lower |= equal & !bit;
+            equal &= bit;
+        } else {
+            equal &= !bit;
+        }
+    }
+    lower
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use std::io::Cursor;
+
+    #[test]
+    fn load() {
+        let payload = indoc! {br"
+            ..@
+            @.@
+        "};
+        let bits = BitGrid::load(Cursor::new(payload)).unwrap();
+        assert_eq!(bits.nb_rolls(), 3);
+        assert_eq!(bits.to_grid(), Grid::load(Cursor::new(payload)).unwrap());
+        let e = BitGrid::load(Cursor::new(b"..@\n@x.\n")).unwrap_err();
+        assert_eq!(e.to_string(), "line 2, column 2: unexpected tile: 'x'");
+        let e = BitGrid::load(Cursor::new(b"..@\n@.\n")).unwrap_err();
+        assert_eq!(e.to_string(), "line 2: expected 3 tiles, found 2");
+        // Longer row with a roll past the words of a row.
+        let payload = format!("..@\n{}@\n", ".".repeat(69));
+        let e = BitGrid::load(Cursor::new(payload)).unwrap_err();
+        assert_eq!(e.to_string(), "line 2: expected 3 tiles, found 70");
+        let e = BitGrid::load(Cursor::new(b"..@\n@\xff.\n")).unwrap_err();
+        assert_eq!(e.to_string(), "line 2, column 2: invalid UTF-8");
+    }
+
+    #[test]
+    fn counts_across_words() {
+        // Rolls at both sides of a word boundary, each with one neighbor.
+        let mut grid = Grid::new(130, 2, RollTile::Empty);
+        grid.set(63, 0, RollTile::Roll);
+        grid.set(64, 1, RollTile::Roll);
+        grid.set(129, 1, RollTile::Roll);
+        let mut bits = BitGrid::from_grid(&grid);
+        assert!(bits.is_roll_at(64, 1));
+        assert_eq!(bits.remove_accessible_rolls(1), 1);
+        assert!(!bits.is_roll_at(129, 1));
+        assert_eq!(bits.remove_accessible_rolls(2), 2);
+        assert_eq!(bits.nb_rolls(), 0);
+    }
+
+    #[test]
+    fn comparison() {
+        let mut count = [0; 4];
+        for _ in 0..5 {
+            add(&mut count, 0b10);
+        }
+        add(&mut count, 0b01);
+        assert_eq!(less_than(&count, 0), 0b00);
+        assert_eq!(less_than(&count, 2) & 0b11, 0b01);
+        assert_eq!(less_than(&count, 6) & 0b11, 0b11);
+        assert_eq!(less_than(&count, 16), !0);
+    }
+}
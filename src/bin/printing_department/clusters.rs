@@ -0,0 +1,117 @@
+use std::io::Write;
+
+use advent_code_25::components::{Cluster, Components};
+
+/// Prints clusters of rolls before and after removal, showing how many rolls
+/// of each initial cluster remain and which ones were fully cleared.
+pub fn write_cluster_report<W: Write>(
+    mut output: W,
+    before: &Components,
+    after: &Components,
+) -> anyhow::Result<()> {
+    // Removal only splits clusters, so each cluster left is part of a single
+    // initial cluster.
+    let mut nb_remaining = vec![0; before.clusters().len()];
+    let origins: Vec<usize> = after
+        .clusters()
+        .iter()
+        .map(|cluster| {
+            let (x, y) = cluster.first;
+            let origin = before
+                .label(x, y)
+                .expect("remaining roll outside initial clusters");
+            nb_remaining[origin] += cluster.size;
+            origin
+        })
+        .collect();
+
+    writeln!(
+        output,
+        "clusters before removal: {}",
+        before.clusters().len()
+    )?;
+    writeln!(
+        output,
+        "{:>7} {:>8} {:<23} {:>9}",
+        "cluster", "size", "bounding box", "remaining"
+    )?;
+    for (i, cluster) in before.clusters().iter().enumerate() {
+        writeln!(
+            output,
+            "{:>7} {:>8} {:<23} {:>9}{}",
+            i,
+            cluster.size,
+            bounding_box(cluster),
+            nb_remaining[i],
+            if nb_remaining[i] == 0 { " cleared" } else { "" }
+        )?;
+    }
+    let nb_cleared = nb_remaining.iter().filter(|&&n| n == 0).count();
+    writeln!(output, "clusters fully cleared: {}", nb_cleared)?;
+
+    writeln!(output, "clusters after removal: {}", after.clusters().len())?;
+    writeln!(
+        output,
+        "{:>7} {:>8} {:<23} {:>9}",
+        "cluster", "size", "bounding box", "from"
+    )?;
+    for (i, (cluster, origin)) in after.clusters().iter().zip(origins).enumerate() {
+        writeln!(
+            output,
+            "{:>7} {:>8} {:<23} {:>9}",
+            i,
+            cluster.size,
+            bounding_box(cluster),
+            origin
+        )?;
+    }
+    Ok(())
+}
+
+fn bounding_box(cluster: &Cluster) -> String {
+    format!(
+        "({}, {})-({}, {})",
+        cluster.min.0, cluster.min.1, cluster.max.0, cluster.max.1
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::{Grid, RollTile};
+    use advent_code_25::neighborhood::{Boundary, Neighborhood};
+    use indoc::indoc;
+    use std::io::Cursor;
+
+    fn components(payload: &[u8]) -> Components {
+        let grid = Grid::load(Cursor::new(payload)).unwrap();
+        grid.components(&Neighborhood::moore(), &Boundary::Clip, |tile| {
+            *tile == RollTile::Roll
+        })
+    }
+
+    #[test]
+    fn report() {
+        let before = components(indoc! {br"
+            @@...
+            @@.@@
+        "});
+        let after = components(indoc! {br"
+            .@...
+            @....
+        "});
+        let mut output = Vec::new();
+        write_cluster_report(&mut output, &before, &after).unwrap();
+        let expected = indoc! {"
+            clusters before removal: 2
+            cluster     size bounding box            remaining
+                  0        4 (0, 0)-(1, 1)                   2
+                  1        2 (3, 1)-(4, 1)                   0 cleared
+            clusters fully cleared: 1
+            clusters after removal: 1
+            cluster     size bounding box                 from
+                  0        2 (0, 0)-(1, 1)                   0
+        "};
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+}
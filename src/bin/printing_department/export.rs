@@ -0,0 +1,122 @@
+use std::io::Write;
+use std::str::FromStr;
+
+use crate::grid::{Grid, RollGridExt};
+
+/// Format in which a grid of rolls can be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    /// Same `.`/`@` text as read by `Grid::load`.
+    #[default]
+    Text,
+    /// One line per row with `1` for rolls and `0` for empty tiles.
+    Csv,
+    /// Array of rows, each an array of booleans true for rolls.
+    Json,
+    /// Plain PBM image with rolls in black.
+    Pbm,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            "pbm" => Ok(Self::Pbm),
+            _ => anyhow::bail!(
+                "unknown export format: {} (expected text, csv, json or pbm)",
+                s
+            ),
+        }
+    }
+}
+
+/// Writes `grid` in given `format`.
+pub fn write_grid<W: Write>(
+    mut output: W,
+    grid: &Grid,
+    format: ExportFormat,
+) -> anyhow::Result<()> {
+    let rows =
+        || (0..grid.height()).map(move |y| (0..grid.width()).map(move |x| grid.is_roll_at(x, y)));
+    match format {
+        ExportFormat::Text => grid.save(&mut output)?,
+        ExportFormat::Csv => {
+            for row in rows() {
+                let cells: Vec<&str> = row.map(|roll| if roll { "1" } else { "0" }).collect();
+                writeln!(output, "{}", cells.join(","))?;
+            }
+        }
+        ExportFormat::Json => {
+            let rows: Vec<String> = rows()
+                .map(|row| {
+                    let cells: Vec<String> = row.map(|roll| roll.to_string()).collect();
+                    format!("[{}]", cells.join(","))
+                })
+                .collect();
+            writeln!(output, "[{}]", rows.join(","))?;
+        }
+        ExportFormat::Pbm => {
+            writeln!(output, "P1")?;
+            writeln!(output, "{} {}", grid.width(), grid.height())?;
+            for row in rows() {
+                let cells: Vec<&str> = row.map(|roll| if roll { "1" } else { "0" }).collect();
+                writeln!(output, "{}", cells.join(" "))?;
+            }
+        }
+    }
+    output.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use std::io::Cursor;
+
+    fn export(format: ExportFormat) -> String {
+        let grid = Grid::load(Cursor::new(b"@.@\n.@.\n")).unwrap();
+        let mut output = Vec::new();
+        write_grid(&mut output, &grid, format).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(export(ExportFormat::Text), "@.@\n.@.\n");
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(export(ExportFormat::Csv), "1,0,1\n0,1,0\n");
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(
+            export(ExportFormat::Json),
+            "[[true,false,true],[false,true,false]]\n"
+        );
+    }
+
+    #[test]
+    fn pbm() {
+        let expected = indoc! {"
+            P1
+            3 2
+            1 0 1
+            0 1 0
+        "};
+        assert_eq!(export(ExportFormat::Pbm), expected);
+    }
+
+    #[test]
+    fn parse_format() {
+        assert_eq!("pbm".parse::<ExportFormat>().unwrap(), ExportFormat::Pbm);
+        assert!("xml".parse::<ExportFormat>().is_err());
+    }
+}
@@ -1,62 +1,146 @@
-use std::io::Read;
+use advent_code_25::grid::{self, Tile};
+use advent_code_25::neighborhood::{Boundary, Neighborhood};
+
+/// A tile either empty or containing a roll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RollTile {
+    Empty,
+    Roll,
+}
+
+impl Tile for RollTile {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' => Some(Self::Empty),
+            '@' => Some(Self::Roll),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        match self {
+            Self::Empty => '.',
+            Self::Roll => '@',
+        }
+    }
+}
 
 /// A rectangular grid with each tile either empty or containing a roll.
-pub struct Grid {
-    content: Vec<u8>,
-    width: usize,
-    height: usize,
+pub type Grid = grid::Grid<RollTile>;
+
+pub trait RollGridExt {
+    fn is_roll_at(&self, x: usize, y: usize) -> bool;
+
+    fn remove_roll_at(&mut self, x: usize, y: usize);
+}
+
+impl RollGridExt for Grid {
+    fn is_roll_at(&self, x: usize, y: usize) -> bool {
+        self[(x, y)] == RollTile::Roll
+    }
+
+    fn remove_roll_at(&mut self, x: usize, y: usize) {
+        debug_assert!(self.is_roll_at(x, y));
+        self.set(x, y, RollTile::Empty);
+    }
 }
 
-impl Grid {
-    pub fn load<R: Read>(mut input: R) -> anyhow::Result<Grid> {
-        let mut content = Vec::new();
-        input.read_to_end(&mut content)?;
-
-        // XXX We should validate input here:
-        //      - check rectangular:
-        //          - check all lines have same size
-        //          - check height divides input size
-        //      - check contains only . or @
-        // XXX Assume end-of-line is \n => Does not work for Windows text file.
-        let width = content
+/// Rolls addressed by signed coordinates, with no roll outside of layout.
+pub trait RollLayout {
+    fn has_roll(&self, x: isize, y: isize) -> bool;
+
+    /// Removes roll at `(x, y)`, which must be there.
+    fn take_roll(&mut self, x: isize, y: isize);
+
+    /// Returns positions of all rolls.
+    fn rolls(&self) -> Vec<(isize, isize)>;
+
+    /// Returns number of rolls in `neighborhood` of `(x, y)`.
+    fn nb_neighboring_rolls(&self, (x, y): (isize, isize), neighborhood: &Neighborhood) -> usize {
+        neighborhood
+            .offsets()
             .iter()
-            .position(|b| *b == b'\n')
-            .unwrap_or(content.len());
-        // XXX We assume last line has an end-of-line.  Failing this height is
-        // off by one.
-        let height = content.len() / (width + 1);
+            .filter(|&&(dx, dy)| self.has_roll(x + dx, y + dy))
+            .count()
+    }
 
-        Ok(Self {
-            content,
-            width,
-            height,
-        })
+    /// Returns positions of tiles in `neighborhood` of `(x, y)`, some of which
+    /// may be out of layout.
+    fn neighbor_positions(
+        &self,
+        (x, y): (isize, isize),
+        neighborhood: &Neighborhood,
+    ) -> Vec<(isize, isize)> {
+        neighborhood
+            .offsets()
+            .iter()
+            .map(|&(dx, dy)| (x + dx, y + dy))
+            .collect()
     }
+}
 
-    pub fn width(&self) -> usize {
-        self.width
+impl RollLayout for Grid {
+    fn has_roll(&self, x: isize, y: isize) -> bool {
+        match (usize::try_from(x), usize::try_from(y)) {
+            (Ok(x), Ok(y)) => self.get(x, y) == Some(&RollTile::Roll),
+            _ => false,
+        }
     }
 
-    pub fn height(&self) -> usize {
-        self.height
+    fn take_roll(&mut self, x: isize, y: isize) {
+        self.remove_roll_at(x as usize, y as usize);
     }
 
-    pub fn is_roll_at(&self, x: usize, y: usize) -> bool {
-        let tile = self.content[self.to_offset(x, y)];
-        debug_assert!(tile == b'@' || tile == b'.');
-        tile == b'@'
+    fn rolls(&self) -> Vec<(isize, isize)> {
+        self.positions()
+            .filter(|&(x, y)| self.is_roll_at(x, y))
+            .map(|(x, y)| (x as isize, y as isize))
+            .collect()
     }
+}
 
-    pub fn remove_roll_at(&mut self, x: usize, y: usize) {
-        debug_assert!(self.is_roll_at(x, y));
-        let offset = self.to_offset(x, y);
-        self.content[offset] = b'.';
+/// Grid whose neighborhoods handle tiles out of grid as told by a boundary,
+/// instead of ignoring them.
+pub struct BoundedGrid<'a> {
+    grid: &'a mut Grid,
+    boundary: &'a Boundary<RollTile>,
+}
+
+impl<'a> BoundedGrid<'a> {
+    pub fn new(grid: &'a mut Grid, boundary: &'a Boundary<RollTile>) -> Self {
+        Self { grid, boundary }
+    }
+}
+
+impl RollLayout for BoundedGrid<'_> {
+    fn has_roll(&self, x: isize, y: isize) -> bool {
+        self.grid.has_roll(x, y)
     }
 
-    fn to_offset(&self, x: usize, y: usize) -> usize {
-        debug_assert!(x < self.width());
-        debug_assert!(y < self.height());
-        y * (self.width + 1) + x
+    fn take_roll(&mut self, x: isize, y: isize) {
+        self.grid.take_roll(x, y);
+    }
+
+    fn rolls(&self) -> Vec<(isize, isize)> {
+        RollLayout::rolls(self.grid)
+    }
+
+    fn nb_neighboring_rolls(&self, (x, y): (isize, isize), neighborhood: &Neighborhood) -> usize {
+        self.grid
+            .neighborhood(x as usize, y as usize, neighborhood, self.boundary)
+            .filter(|tile| **tile == RollTile::Roll)
+            .count()
+    }
+
+    fn neighbor_positions(
+        &self,
+        (x, y): (isize, isize),
+        neighborhood: &Neighborhood,
+    ) -> Vec<(isize, isize)> {
+        self.grid
+            .neighborhood_positions(x as usize, y as usize, neighborhood, self.boundary)
+            .map(|(x, y)| (x as isize, y as isize))
+            .collect()
     }
 }
 
@@ -86,6 +170,42 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn reject_unknown_tiles() {
+        let e = Grid::load(Cursor::new(b"..@\n@x.\n")).unwrap_err();
+        assert_eq!(e.to_string(), "line 2, column 2: unexpected tile: 'x'");
+    }
+
+    #[test]
+    fn layout() {
+        let mut grid = Grid::load(Cursor::new(b".@\n@@\n")).unwrap();
+        assert!(grid.has_roll(1, 0));
+        assert!(!grid.has_roll(0, 0));
+        assert!(!grid.has_roll(-1, 0));
+        assert!(!grid.has_roll(1, 2));
+        grid.take_roll(1, 1);
+        assert_eq!(RollLayout::rolls(&grid), vec![(1, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn bounded_layout() {
+        let mut grid = Grid::load(Cursor::new(b".@\n@@\n")).unwrap();
+        let moore = Neighborhood::moore();
+        assert_eq!(grid.nb_neighboring_rolls((0, 0), &moore), 3);
+        assert_eq!(grid.neighbor_positions((0, 0), &moore).len(), 8);
+        let wrap = Boundary::Wrap;
+        let wrapped = BoundedGrid::new(&mut grid, &wrap);
+        // Each of the other tiles is a neighbor 2 or 4 times over.
+        assert_eq!(wrapped.nb_neighboring_rolls((0, 0), &moore), 2 + 2 + 4);
+        assert_eq!(wrapped.neighbor_positions((1, 1), &moore).len(), 8);
+        let pad = Boundary::Pad(RollTile::Roll);
+        let mut padded = BoundedGrid::new(&mut grid, &pad);
+        assert_eq!(padded.nb_neighboring_rolls((0, 0), &moore), 5 + 3);
+        assert_eq!(padded.neighbor_positions((0, 0), &moore).len(), 3);
+        padded.take_roll(1, 1);
+        assert_eq!(padded.rolls(), vec![(1, 0), (0, 1)]);
+    }
+
     #[test]
     fn remove_roll() {
         let payload = indoc! {br"
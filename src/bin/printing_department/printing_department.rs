@@ -1,82 +1,855 @@
 // Day 4 - Printing Department
 
-use std::cmp::min;
-use std::io::BufRead;
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Write, stdout};
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
 
+use advent_code_25::CommandLine;
+use advent_code_25::automaton::{Automaton, Rule};
+use advent_code_25::checkpoint::{self, Checkpoint, Checkpointer, Fingerprint};
+use advent_code_25::cycle::Cycle;
+use advent_code_25::neighborhood::{Boundary, Neighborhood};
+
+mod bitgrid;
+use bitgrid::BitGrid;
+mod clusters;
+mod export;
+use export::ExportFormat;
 mod grid;
-use grid::Grid;
+use grid::{BoundedGrid, Grid, RollGridExt, RollLayout, RollTile};
+mod sparse;
+use sparse::SparseGrid;
+mod timeline;
+use timeline::{ImageFormat, Timeline};
+
+/// Number of neighboring rolls from which a roll is no longer accessible.
+const DEFAULT_THRESHOLD: usize = 4;
+
+/// Default delay between frames of animation, in milliseconds.
+const DEFAULT_FRAME_DELAY: u64 = 100;
+
+/// Default size of tiles in frame images, in pixels.
+const DEFAULT_SCALE: usize = 4;
 
 fn main() -> anyhow::Result<()> {
-    let input = advent_code_25::open_input_file()?;
-    let answer = solve_for(input)?;
+    run(&CommandLine::from_env())
+}
+
+/// Solves puzzle as told by `cmd_line`, printing results.
+fn run(cmd_line: &CommandLine) -> anyhow::Result<()> {
+    let accessibility = Accessibility::from_command_line(cmd_line)?;
+    let save_path: Option<PathBuf> = cmd_line.option("save")?;
+    let save_format: ExportFormat = cmd_line.option("save-format")?.unwrap_or_default();
+    let save = |grid: &Grid| match &save_path {
+        Some(path) => save_grid(path, grid, save_format),
+        None => Ok(()),
+    };
+    let clusters = cmd_line.flag("clusters");
+    let report_clusters = |initial: &Grid, grid: &Grid| {
+        if !clusters {
+            return Ok(());
+        }
+        let components = |grid: &Grid| {
+            grid.components(
+                &accessibility.neighborhood,
+                &accessibility.boundary,
+                |tile| *tile == RollTile::Roll,
+            )
+        };
+        clusters::write_cluster_report(stdout().lock(), &components(initial), &components(grid))
+    };
+    let checkpointer = Checkpointer::from_command_line(cmd_line)?;
+    let input = cmd_line.open_input_file()?;
+
+    if cmd_line.positional(1) == Some("inspect") {
+        if checkpointer.is_some() {
+            anyhow::bail!("--checkpoint is not supported with inspect");
+        }
+        let mut grid = Grid::load(input)?;
+        let passes = Passes::generations_from_command_line(cmd_line)?;
+        let timeline = remove_rolls_by_scanning(&mut grid, &accessibility, &passes);
+        save(&grid)?;
+        report_clusters(timeline.initial(), &grid)?;
+        return run_inspect(cmd_line, &timeline, &accessibility);
+    }
+
+    if let Some(rule) = cmd_line.option("rule")? {
+        if checkpointer.is_some() {
+            anyhow::bail!("--checkpoint is not supported with --rule");
+        }
+        if clusters {
+            anyhow::bail!("--clusters is not supported with --rule");
+        }
+        let mut grid = Grid::load(input)?;
+        let nb_initial_rolls = RollLayout::rolls(&grid).len();
+        let automaton = roll_automaton(rule, &accessibility);
+        let nb_steps = cmd_line.option("steps")?;
+        match automaton.run(&mut grid, nb_steps) {
+            None => println!("no repeated generation"),
+            Some(cycle) if cycle.is_fixed_point() => {
+                println!("fixed point at generation {}", cycle.start)
+            }
+            Some(Cycle { start, period }) => {
+                println!("cycle from generation {} with period {}", start, period)
+            }
+        }
+        println!(
+            "rolls: {} -> {}",
+            nb_initial_rolls,
+            RollLayout::rolls(&grid).len()
+        );
+        save(&grid)?;
+        return Ok(());
+    }
+
+    let annotate = cmd_line.flag("annotate");
+    let animate = cmd_line.flag("animate");
+    let frames_directory: Option<PathBuf> = cmd_line.option("frames")?;
+    if annotate || animate || frames_directory.is_some() {
+        if checkpointer.is_some() {
+            anyhow::bail!("--checkpoint is not supported with --annotate, --animate or --frames");
+        }
+        let mut grid = Grid::load(input)?;
+        let passes = Passes::generations_from_command_line(cmd_line)?;
+        let timeline = remove_rolls_by_scanning(&mut grid, &accessibility, &passes);
+        if animate {
+            let delay = cmd_line.option("delay")?.unwrap_or(DEFAULT_FRAME_DELAY);
+            timeline.animate(stdout().lock(), Duration::from_millis(delay))?;
+        }
+        if let Some(directory) = frames_directory {
+            let format: ImageFormat = cmd_line.option("frame-format")?.unwrap_or_default();
+            let scale = cmd_line.option("scale")?.unwrap_or(DEFAULT_SCALE);
+            timeline.write_frames(&directory, format, scale)?;
+        }
+        if annotate {
+            timeline.write_annotated(stdout().lock())?;
+        }
+        save(&grid)?;
+        report_clusters(timeline.initial(), &grid)?;
+        let answer: usize = timeline.nb_removed_per_generation().iter().sum();
+        println!("answer: {}", answer);
+        return Ok(());
+    }
+
+    let report =
+        |pass, nb_removed_rolls| println!("pass {}: {} rolls removed", pass, nb_removed_rolls);
+    let answer = if cmd_line.flag("bitset") {
+        if checkpointer.is_some() {
+            anyhow::bail!("--checkpoint is not supported with --bitset");
+        }
+        if save_path.is_some() {
+            anyhow::bail!("--save is not supported with --bitset");
+        }
+        if clusters {
+            anyhow::bail!("--clusters is not supported with --bitset");
+        }
+        let passes = Passes::generations_from_command_line(cmd_line)?;
+        solve_with_bitset(input, &accessibility, &passes, report)?
+    } else if cmd_line.flag("sparse") {
+        if checkpointer.is_some() {
+            anyhow::bail!("--checkpoint is not supported with --sparse");
+        }
+        if save_path.is_some() {
+            anyhow::bail!("--save is not supported with --sparse");
+        }
+        if clusters {
+            anyhow::bail!("--clusters is not supported with --sparse");
+        }
+        if Passes::from_command_line(cmd_line, RemovalMode::default())?.is_some() {
+            anyhow::bail!(
+                "--scan, --mode, --jobs and --max-passes are not supported with --sparse"
+            );
+        }
+        solve_with_sparse_grid(input, &accessibility)?
+    } else {
+        let passes = Passes::from_command_line(cmd_line, RemovalMode::default())?;
+        let grid;
+        let mut initial = None;
+        let answer = match checkpointer {
+            Some(mut checkpointer) => {
+                let passes = passes.unwrap_or_default();
+                // Number of jobs and of passes do not change results.
+                let fingerprint = Fingerprint::of_input(cmd_line.open_input_file()?)?
+                    .with(format!("{:?}", accessibility))
+                    .with(format!("{:?}", passes.mode));
+                let mut state =
+                    checkpointer.start(fingerprint, || Ok(PassState::new(Grid::load(input)?)))?;
+                if clusters {
+                    initial = Some(state.grid.clone());
+                }
+                let answer = solve_with_checkpoints(
+                    &mut state,
+                    &accessibility,
+                    &passes,
+                    &mut checkpointer,
+                    report,
+                )?;
+                if passes.max_passes == Some(state.nb_passes) {
+                    // Stopped early, keep state to resume from later.
+                    checkpointer.save(&state)?;
+                } else {
+                    checkpointer.finish()?;
+                }
+                grid = state.grid;
+                answer
+            }
+            None => {
+                let mut loaded = Grid::load(input)?;
+                if clusters {
+                    initial = Some(loaded.clone());
+                }
+                let answer = solve_for(&mut loaded, &accessibility, passes, report);
+                grid = loaded;
+                answer
+            }
+        };
+        if let Some(initial) = initial {
+            report_clusters(&initial, &grid)?;
+        }
+        save(&grid)?;
+        answer
+    };
     println!("answer: {}", answer);
     Ok(())
 }
 
-fn solve_for<R: BufRead>(input: R) -> anyhow::Result<usize> {
-    let mut grid = Grid::load(input)?;
-    let mut nb_removed_rolls = 0;
-    // Compute fixed point.
-    loop {
-        let n = remove_accessible_rolls(&mut grid, false);
+/// Writes `grid` to file at `path`.
+fn save_grid(path: &Path, grid: &Grid, format: ExportFormat) -> anyhow::Result<()> {
+    let file = File::create(path)
+        .map_err(|e| anyhow::anyhow!("failed to create {}: {}", path.display(), e))?;
+    export::write_grid(BufWriter::new(file), grid, format)
+}
+
+/// Returns automaton applying `rule` to rolls, neighbors being those of
+/// `accessibility`.  Rule `Rule::removal` makes it remove accessible rolls
+/// synchronously.
+fn roll_automaton(rule: Rule, accessibility: &Accessibility) -> Automaton<RollTile> {
+    Automaton::new(
+        rule,
+        accessibility.neighborhood.clone(),
+        accessibility.boundary.clone(),
+        RollTile::Roll,
+        RollTile::Empty,
+    )
+}
+
+/// Prints what happened to tile at coordinates given as 3rd and 4th positional
+/// arguments, or remaining rolls if there are no coordinates.
+fn run_inspect(
+    cmd_line: &CommandLine,
+    timeline: &Timeline,
+    accessibility: &Accessibility,
+) -> anyhow::Result<()> {
+    let coordinate = |i: usize| -> anyhow::Result<Option<usize>> {
+        cmd_line
+            .positional(i)
+            .map(|s| {
+                s.parse()
+                    .map_err(|e| anyhow::anyhow!("invalid coordinate: {}: {}", s, e))
+            })
+            .transpose()
+    };
+    let (x, y) = match (coordinate(2)?, coordinate(3)?) {
+        (Some(x), Some(y)) => (x, y),
+        (None, _) => {
+            let remaining: Vec<_> = timeline.remaining_rolls().collect();
+            println!("remaining rolls: {}", remaining.len());
+            for (x, y) in remaining {
+                println!("{} {}", x, y);
+            }
+            return Ok(());
+        }
+        (Some(_), None) => anyhow::bail!("missing y coordinate"),
+    };
+    let grid = timeline.initial();
+    if x >= grid.width() || y >= grid.height() {
+        anyhow::bail!(
+            "({}, {}) out of grid of {}x{} tiles",
+            x,
+            y,
+            grid.width(),
+            grid.height()
+        );
+    }
+
+    match timeline.removed_in(x, y) {
+        Some(generation) => println!("({}, {}): removed in generation {}", x, y, generation),
+        None if grid.is_roll_at(x, y) => println!("({}, {}): never removed", x, y),
+        None => println!("({}, {}): no roll", x, y),
+    }
+    println!("generation neighbors");
+    let counts = nb_neighboring_rolls_per_generation(timeline, accessibility, x, y);
+    for (generation, count) in counts.iter().enumerate() {
+        let accessible =
+            count < &accessibility.threshold && timeline.is_roll_after(x, y, generation);
+        println!(
+            "{:>10} {:>9}{}",
+            generation,
+            count,
+            if accessible { " accessible" } else { "" }
+        );
+    }
+    Ok(())
+}
+
+/// Rule deciding whether a roll can be reached and removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Accessibility {
+    neighborhood: Neighborhood,
+    boundary: Boundary<RollTile>,
+    /// A roll is accessible if fewer than this number of its neighbors are
+    /// rolls.
+    threshold: usize,
+}
+
+impl Default for Accessibility {
+    fn default() -> Self {
+        Self {
+            neighborhood: Neighborhood::moore(),
+            boundary: Boundary::Clip,
+            threshold: DEFAULT_THRESHOLD,
+        }
+    }
+}
+
+impl Accessibility {
+    /// Builds rule from `--neighborhood`, `--boundary` and `--threshold`
+    /// options, using defaults for missing ones.
+    fn from_command_line(cmd_line: &CommandLine) -> anyhow::Result<Self> {
+        let default = Self::default();
+        Ok(Self {
+            neighborhood: cmd_line
+                .option("neighborhood")?
+                .unwrap_or(default.neighborhood),
+            boundary: cmd_line.option("boundary")?.unwrap_or(default.boundary),
+            threshold: cmd_line.option("threshold")?.unwrap_or(default.threshold),
+        })
+    }
+}
+
+/// How rolls accessible during a pass over the grid are removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum RemovalMode {
+    /// Rolls are removed as soon as they are found accessible, so removals
+    /// earlier in the pass (in row-major order) affect later rolls.
+    #[default]
+    InPlace,
+    /// Accessibility of all rolls is decided on the grid as it was at the
+    /// start of the pass, and accessible rolls are removed together.
+    Synchronous,
+}
+
+impl FromStr for RemovalMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "in-place" => Ok(Self::InPlace),
+            "synchronous" => Ok(Self::Synchronous),
+            _ => anyhow::bail!(
+                "unknown removal mode: {} (expected in-place or synchronous)",
+                s
+            ),
+        }
+    }
+}
+
+/// How to make passes over the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Passes {
+    mode: RemovalMode,
+    /// Number of threads sharing each synchronous pass.
+    nb_jobs: usize,
+    /// Number of passes after which to stop, even if some rolls are still
+    /// accessible.
+    max_passes: Option<usize>,
+}
+
+impl Default for Passes {
+    fn default() -> Self {
+        RemovalMode::default().into()
+    }
+}
+
+impl From<RemovalMode> for Passes {
+    fn from(mode: RemovalMode) -> Self {
+        Self {
+            mode,
+            nb_jobs: 1,
+            max_passes: None,
+        }
+    }
+}
+
+impl Passes {
+    /// Builds passes from `--scan`, `--mode`, `--jobs` and `--max-passes`
+    /// options, or returns `None` if none is given.  Passes are in
+    /// `default_mode` unless `--mode` is given, giving `--jobs` implying
+    /// synchronous mode.
+    fn from_command_line(
+        cmd_line: &CommandLine,
+        default_mode: RemovalMode,
+    ) -> anyhow::Result<Option<Self>> {
+        let nb_jobs = cmd_line.option::<NonZeroUsize>("jobs")?;
+        let max_passes = cmd_line.option("max-passes")?;
+        let mode = match cmd_line.option::<RemovalMode>("mode")? {
+            Some(RemovalMode::InPlace) if nb_jobs.is_some() => {
+                anyhow::bail!("--jobs requires synchronous removal mode")
+            }
+            Some(mode) => mode,
+            None if nb_jobs.is_some() => RemovalMode::Synchronous,
+            None if cmd_line.flag("scan") || max_passes.is_some() => default_mode,
+            None => return Ok(None),
+        };
+        Ok(Some(Self {
+            mode,
+            nb_jobs: nb_jobs.map_or(1, NonZeroUsize::get),
+            max_passes,
+        }))
+    }
+
+    /// Builds passes like `from_command_line`, synchronous unless `--mode`
+    /// says otherwise so that each pass is a generation.
+    fn generations_from_command_line(cmd_line: &CommandLine) -> anyhow::Result<Self> {
+        let synchronous = RemovalMode::Synchronous;
+        Ok(Self::from_command_line(cmd_line, synchronous)?.unwrap_or(synchronous.into()))
+    }
+}
+
+/// Removes rolls from `grid` and returns their number.
+///
+/// With `passes`, the whole grid is rescanned until a pass removes nothing
+/// and `report` is called with the number (starting at 1) and removal count
+/// of each pass.  Otherwise, only rolls that may have become accessible are
+/// revisited and `report` is never called.
+fn solve_for(
+    grid: &mut Grid,
+    accessibility: &Accessibility,
+    passes: Option<Passes>,
+    mut report: impl FnMut(usize, usize),
+) -> usize {
+    match passes {
+        Some(passes) => {
+            let timeline = remove_rolls_by_scanning(grid, accessibility, &passes);
+            let nb_removed_per_pass = timeline.nb_removed_per_generation();
+            for (i, &n) in nb_removed_per_pass.iter().enumerate() {
+                report(i + 1, n);
+            }
+            nb_removed_per_pass.iter().sum()
+        }
+        None => remove_rolls_incrementally(grid, accessibility),
+    }
+}
+
+/// Progress of pass-by-pass removal, saved in checkpoints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PassState {
+    grid: Grid,
+    nb_passes: usize,
+    nb_removed_rolls: usize,
+}
+
+impl PassState {
+    fn new(grid: Grid) -> Self {
+        Self {
+            grid,
+            nb_passes: 0,
+            nb_removed_rolls: 0,
+        }
+    }
+}
+
+impl Checkpoint for PassState {
+    fn save<W: Write>(&self, mut output: W) -> anyhow::Result<()> {
+        checkpoint::write_field(&mut output, "passes", self.nb_passes)?;
+        checkpoint::write_field(&mut output, "removed", self.nb_removed_rolls)?;
+        self.grid.save(output)?;
+        Ok(())
+    }
+
+    fn restore<R: BufRead>(mut input: R) -> anyhow::Result<Self> {
+        Ok(Self {
+            nb_passes: checkpoint::read_field(&mut input, "passes")?,
+            nb_removed_rolls: checkpoint::read_field(&mut input, "removed")?,
+            grid: Grid::load(input)?,
+        })
+    }
+}
+
+/// Removes rolls from grid of `state` one pass at a time like
+/// `remove_rolls_by_scanning`, offering state to `checkpointer` after each
+/// pass.  Calls `report` with the number and removal count of each pass.
+/// Returns number of removed rolls, including those removed before state was
+/// saved.
+fn solve_with_checkpoints(
+    state: &mut PassState,
+    accessibility: &Accessibility,
+    passes: &Passes,
+    checkpointer: &mut Checkpointer,
+    mut report: impl FnMut(usize, usize),
+) -> anyhow::Result<usize> {
+    while passes.max_passes != Some(state.nb_passes) {
+        let removed = remove_pass(&mut state.grid, accessibility, passes);
+        if removed.is_empty() {
+            break;
+        }
+        state.nb_passes += 1;
+        state.nb_removed_rolls += removed.len();
+        report(state.nb_passes, removed.len());
+        checkpointer.maybe_save(state)?;
+    }
+    Ok(state.nb_removed_rolls)
+}
+
+/// Returns number of rolls removed by `passes`, removing them synchronously one
+/// pass at a time on a bit-packed grid.  Calls `report` with the number and
+/// removal count of each pass.
+fn solve_with_bitset<R: BufRead>(
+    input: R,
+    accessibility: &Accessibility,
+    passes: &Passes,
+    mut report: impl FnMut(usize, usize),
+) -> anyhow::Result<usize> {
+    if accessibility.neighborhood != Neighborhood::moore()
+        || accessibility.boundary != Boundary::Clip
+    {
+        anyhow::bail!("bitset grid supports only moore neighborhood with clip boundary");
+    }
+    if passes.mode != RemovalMode::Synchronous {
+        anyhow::bail!("bitset grid supports only synchronous removal mode");
+    }
+    if passes.nb_jobs > 1 {
+        anyhow::bail!("bitset grid supports only one job");
+    }
+    let mut grid = BitGrid::load(input)?;
+    let nb_initial_rolls = grid.nb_rolls();
+    for pass in 1.. {
+        if passes.max_passes == Some(pass - 1) {
+            break;
+        }
+        let n = grid.remove_accessible_rolls(accessibility.threshold);
         if n == 0 {
-            break
+            break;
         }
-        nb_removed_rolls += n;
+        report(pass, n);
     }
-    Ok(nb_removed_rolls)
+    Ok(nb_initial_rolls - grid.nb_rolls())
 }
 
-/// Removes from `grid` all accessible rolls.
-/// Returns number of removed rolls.
-fn remove_accessible_rolls(grid: &mut Grid, debug: bool) -> usize {
-    let mut sum = 0;
-    for y in 0..grid.height() {
-        for x in 0..grid.width() {
-            if grid.is_roll_at(x, y) {
-                if nb_neighboring_rolls(grid, x, y) < 4 {
-                    grid.remove_roll_at(x, y);
-                    sum += 1;
-                    if debug {
-                        print!("x");
-                    }
-                } else if debug {
-                    print!("@");
+/// Returns number of rolls that can be removed from layout stored as a sparse
+/// set of rolls, so that lines of `input` may have any length.
+fn solve_with_sparse_grid<R: BufRead>(
+    input: R,
+    accessibility: &Accessibility,
+) -> anyhow::Result<usize> {
+    if accessibility.boundary != Boundary::Clip {
+        anyhow::bail!("sparse grid supports only clip boundary");
+    }
+    let mut grid = SparseGrid::load(input)?;
+    Ok(remove_rolls_from_layout(
+        &mut grid,
+        &accessibility.neighborhood,
+        accessibility.threshold,
+    ))
+}
+
+/// Removes rolls from `layout` until none has fewer than `threshold` rolls in
+/// its `neighborhood`.  Returns number of removed rolls.
+///
+/// Neighbor counts are computed once and then updated as rolls are removed,
+/// so that only rolls whose count just dropped below the threshold are
+/// revisited.  Only tiles with rolls and their neighbors are ever looked at, so
+/// that layout does not need to be a rectangle.
+fn remove_rolls_from_layout<L: RollLayout>(
+    layout: &mut L,
+    neighborhood: &Neighborhood,
+    threshold: usize,
+) -> usize {
+    let mut counts = HashMap::new();
+    let mut pending = VecDeque::new();
+    for roll in layout.rolls() {
+        let n = layout.nb_neighboring_rolls(roll, neighborhood);
+        counts.insert(roll, n);
+        if n < threshold {
+            pending.push_back(roll);
+        }
+    }
+
+    // Rolls whose neighborhood contains a given roll.
+    let reversed = neighborhood.reversed();
+    let mut nb_removed_rolls = 0;
+    while let Some((x, y)) = pending.pop_front() {
+        if !layout.has_roll(x, y) {
+            continue;
+        }
+        layout.take_roll(x, y);
+        nb_removed_rolls += 1;
+        for neighbor in layout.neighbor_positions((x, y), &reversed) {
+            if layout.has_roll(neighbor.0, neighbor.1) {
+                let count = counts.get_mut(&neighbor).expect("roll without count");
+                *count -= 1;
+                if *count + 1 == threshold {
+                    pending.push_back(neighbor);
                 }
-            } else if debug {
-                print!(".");
             }
         }
-        if debug {
-            println!();
+    }
+    nb_removed_rolls
+}
+
+/// Removes accessible rolls from `grid` one pass at a time until a pass
+/// removes nothing or the maximum number of passes is reached.  Returns
+/// timeline of the passes, last empty pass excluded.
+fn remove_rolls_by_scanning(
+    grid: &mut Grid,
+    accessibility: &Accessibility,
+    passes: &Passes,
+) -> Timeline {
+    let mut timeline = Timeline::new(grid.clone());
+    // Compute fixed point.
+    while passes.max_passes != Some(timeline.nb_generations()) {
+        let removed = remove_pass(grid, accessibility, passes);
+        if removed.is_empty() {
+            break;
         }
+        timeline.record_generation(&removed);
     }
-    sum
+    timeline
 }
 
-/// Returns number of rolls adjacent to roll at `(x, y)`.
-fn nb_neighboring_rolls(grid: &Grid, x_roll: usize, y_roll: usize) -> usize {
-    debug_assert!(grid.is_roll_at(x_roll, y_roll));
+/// Makes one pass over `grid` as specified by `passes`.  Returns positions of
+/// removed rolls in row-major order.
+fn remove_pass(
+    grid: &mut Grid,
+    accessibility: &Accessibility,
+    passes: &Passes,
+) -> Vec<(usize, usize)> {
+    match passes.mode {
+        RemovalMode::Synchronous if passes.nb_jobs > 1 => {
+            remove_accessible_rolls_in_parallel(grid, accessibility, passes.nb_jobs)
+        }
+        mode => remove_accessible_rolls(grid, accessibility, mode),
+    }
+}
 
-    let mut nb_rolls = 0;
+/// Removes accessible rolls from `grid` until none is left.  Returns number of
+/// removed rolls.
+fn remove_rolls_incrementally(grid: &mut Grid, accessibility: &Accessibility) -> usize {
+    remove_rolls_from_layout(
+        &mut BoundedGrid::new(grid, &accessibility.boundary),
+        &accessibility.neighborhood,
+        accessibility.threshold,
+    )
+}
 
-    for y in y_roll.saturating_sub(1)..=min(y_roll + 1, grid.height() - 1) {
-        for x in x_roll.saturating_sub(1)..=min(x_roll + 1, grid.width() - 1) {
-            if grid.is_roll_at(x, y) {
-                nb_rolls += 1;
+/// Removes from `grid` all accessible rolls in a single pass.
+/// Returns positions of removed rolls in row-major order.
+fn remove_accessible_rolls(
+    grid: &mut Grid,
+    accessibility: &Accessibility,
+    mode: RemovalMode,
+) -> Vec<(usize, usize)> {
+    let mut accessible_rolls = Vec::new();
+    for (x, y) in grid.positions() {
+        if grid.is_roll_at(x, y)
+            && nb_neighboring_rolls(grid, accessibility, x, y) < accessibility.threshold
+        {
+            if mode == RemovalMode::InPlace {
+                grid.remove_roll_at(x, y);
             }
+            accessible_rolls.push((x, y));
+        }
+    }
+    if mode == RemovalMode::Synchronous {
+        for &(x, y) in &accessible_rolls {
+            grid.remove_roll_at(x, y);
         }
     }
+    accessible_rolls
+}
+
+/// Removes from `grid` all rolls accessible at the start of the pass, like a
+/// synchronous pass, with rows split into `nb_jobs` bands processed by as many
+/// threads.  Each thread reads its own band in place and copies of the halo
+/// rows within reach of the neighborhood above and below it, taken before any
+/// band changes.  Returns positions of removed rolls in row-major order.
+fn remove_accessible_rolls_in_parallel(
+    grid: &mut Grid,
+    accessibility: &Accessibility,
+    nb_jobs: usize,
+) -> Vec<(usize, usize)> {
+    let (width, height) = (grid.width(), grid.height());
+    let band_height = height.div_ceil(nb_jobs).max(1);
+    let halos: Vec<_> = (0..height)
+        .step_by(band_height)
+        .map(|top| halo_rows(grid, accessibility, top..(top + band_height).min(height)))
+        .collect();
+    thread::scope(|scope| {
+        let workers: Vec<_> = grid
+            .row_bands_mut(band_height)
+            .zip(halos)
+            .enumerate()
+            .map(|(i, (band, halo))| {
+                scope.spawn(move || {
+                    let top = i * band_height;
+                    let bottom = top + band.len() / width;
+                    let tile_at = |x: usize, y: usize| {
+                        if (top..bottom).contains(&y) {
+                            band[(y - top) * width + x]
+                        } else {
+                            halo[&y][x]
+                        }
+                    };
+                    let removed: Vec<_> = (top..bottom)
+                        .flat_map(|y| (0..width).map(move |x| (x, y)))
+                        .filter(|&(x, y)| {
+                            tile_at(x, y) == RollTile::Roll
+                                && nb_neighboring_rolls_with(
+                                    tile_at,
+                                    width,
+                                    height,
+                                    accessibility,
+                                    x,
+                                    y,
+                                ) < accessibility.threshold
+                        })
+                        .collect();
+                    for &(x, y) in &removed {
+                        band[(y - top) * width + x] = RollTile::Empty;
+                    }
+                    removed
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("worker thread panicked"))
+            .collect()
+    })
+}
+
+/// Returns copies of rows outside `band` that neighborhoods of its tiles
+/// reach, by row number.
+fn halo_rows(
+    grid: &Grid,
+    accessibility: &Accessibility,
+    band: Range<usize>,
+) -> HashMap<usize, Vec<RollTile>> {
+    let height = grid.height() as isize;
+    let reach = accessibility
+        .neighborhood
+        .offsets()
+        .iter()
+        .map(|&(_, dy)| dy.unsigned_abs())
+        .max()
+        .unwrap_or(0) as isize;
+    let (top, bottom) = (band.start as isize, band.end as isize);
+    (1..=reach)
+        .flat_map(|dy| [top - dy, bottom - 1 + dy])
+        .filter_map(|y| match accessibility.boundary {
+            _ if (0..height).contains(&y) => Some(y as usize),
+            Boundary::Wrap => Some(y.rem_euclid(height) as usize),
+            _ => None,
+        })
+        .filter(|y| !band.contains(y))
+        .map(|y| (y, grid.row(y).to_vec()))
+        .collect()
+}
+
+/// Returns number of rolls in neighborhood of `(x, y)` in a grid of given
+/// size whose tiles are given by `tile_at`.
+fn nb_neighboring_rolls_with(
+    tile_at: impl Fn(usize, usize) -> RollTile,
+    width: usize,
+    height: usize,
+    accessibility: &Accessibility,
+    x: usize,
+    y: usize,
+) -> usize {
+    let (width, height) = (width as isize, height as isize);
+    accessibility
+        .neighborhood
+        .offsets()
+        .iter()
+        .filter(|&&(dx, dy)| {
+            let (nx, ny) = (x as isize + dx, y as isize + dy);
+            let tile = if (0..width).contains(&nx) && (0..height).contains(&ny) {
+                tile_at(nx as usize, ny as usize)
+            } else {
+                match accessibility.boundary {
+                    Boundary::Clip => return false,
+                    Boundary::Wrap => tile_at(
+                        nx.rem_euclid(width) as usize,
+                        ny.rem_euclid(height) as usize,
+                    ),
+                    Boundary::Pad(tile) => tile,
+                }
+            };
+            tile == RollTile::Roll
+        })
+        .count()
+}
+
+/// Returns number of rolls in neighborhood of `(x, y)` once each generation of
+/// `timeline` is over, starting with initial grid.  Tile at `(x, y)` does not
+/// need to be a roll.
+fn nb_neighboring_rolls_per_generation(
+    timeline: &Timeline,
+    accessibility: &Accessibility,
+    x: usize,
+    y: usize,
+) -> Vec<usize> {
+    let grid = timeline.initial();
+    let positions: Vec<_> = grid
+        .neighborhood_positions(x, y, &accessibility.neighborhood, &accessibility.boundary)
+        .collect();
+    let nb_padding_rolls = match accessibility.boundary {
+        Boundary::Pad(RollTile::Roll) => {
+            accessibility.neighborhood.offsets().len() - positions.len()
+        }
+        _ => 0,
+    };
+    (0..=timeline.nb_generations())
+        .map(|generation| {
+            let nb_rolls = positions
+                .iter()
+                .filter(|&&(nx, ny)| timeline.is_roll_after(nx, ny, generation))
+                .count();
+            nb_padding_rolls + nb_rolls
+        })
+        .collect()
+}
 
-    // We counted the roll itself so remove it.
-    debug_assert!(nb_rolls >= 1);
-    nb_rolls - 1
+/// Returns number of rolls in neighborhood of roll at `(x, y)`.
+fn nb_neighboring_rolls(
+    grid: &Grid,
+    accessibility: &Accessibility,
+    x_roll: usize,
+    y_roll: usize,
+) -> usize {
+    debug_assert!(grid.is_roll_at(x_roll, y_roll));
+
+    grid.neighborhood(
+        x_roll,
+        y_roll,
+        &accessibility.neighborhood,
+        &accessibility.boundary,
+    )
+    .filter(|tile| **tile == RollTile::Roll)
+    .count()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use indoc::indoc;
+    use proptest::prelude::*;
     use std::io::Cursor;
 
     fn new_grid(content: &[u8]) -> Grid {
@@ -90,7 +863,11 @@ mod tests {
             .@.
             ...
         "});
-        assert_eq!(remove_accessible_rolls(&mut grid, false), 1);
+        assert_eq!(
+            remove_accessible_rolls(&mut grid, &Accessibility::default(), RemovalMode::InPlace)
+                .len(),
+            1
+        );
     }
 
     #[test]
@@ -100,7 +877,11 @@ mod tests {
             .@.
             @.@
         "});
-        assert_eq!(remove_accessible_rolls(&mut grid, false), 5);
+        assert_eq!(
+            remove_accessible_rolls(&mut grid, &Accessibility::default(), RemovalMode::InPlace)
+                .len(),
+            5
+        );
     }
 
     #[test]
@@ -110,7 +891,10 @@ mod tests {
             .@.
             ...
         "});
-        assert_eq!(nb_neighboring_rolls(&grid, 1, 1), 0);
+        assert_eq!(
+            nb_neighboring_rolls(&grid, &Accessibility::default(), 1, 1),
+            0
+        );
     }
 
     #[test]
@@ -120,7 +904,10 @@ mod tests {
             @@@
             @@@
         "});
-        assert_eq!(nb_neighboring_rolls(&grid, 1, 1), 8);
+        assert_eq!(
+            nb_neighboring_rolls(&grid, &Accessibility::default(), 1, 1),
+            8
+        );
     }
 
     #[test]
@@ -130,13 +917,645 @@ mod tests {
             @.@
             @@@
         "});
-        assert_eq!(nb_neighboring_rolls(&grid, 0, 0), 2);
-        assert_eq!(nb_neighboring_rolls(&grid, 1, 0), 4);
-        assert_eq!(nb_neighboring_rolls(&grid, 2, 0), 2);
-        assert_eq!(nb_neighboring_rolls(&grid, 1, 0), 4);
-        assert_eq!(nb_neighboring_rolls(&grid, 1, 2), 4);
-        assert_eq!(nb_neighboring_rolls(&grid, 0, 2), 2);
-        assert_eq!(nb_neighboring_rolls(&grid, 1, 2), 4);
-        assert_eq!(nb_neighboring_rolls(&grid, 2, 2), 2);
+        assert_eq!(
+            nb_neighboring_rolls(&grid, &Accessibility::default(), 0, 0),
+            2
+        );
+        assert_eq!(
+            nb_neighboring_rolls(&grid, &Accessibility::default(), 1, 0),
+            4
+        );
+        assert_eq!(
+            nb_neighboring_rolls(&grid, &Accessibility::default(), 2, 0),
+            2
+        );
+        assert_eq!(
+            nb_neighboring_rolls(&grid, &Accessibility::default(), 1, 0),
+            4
+        );
+        assert_eq!(
+            nb_neighboring_rolls(&grid, &Accessibility::default(), 1, 2),
+            4
+        );
+        assert_eq!(
+            nb_neighboring_rolls(&grid, &Accessibility::default(), 0, 2),
+            2
+        );
+        assert_eq!(
+            nb_neighboring_rolls(&grid, &Accessibility::default(), 1, 2),
+            4
+        );
+        assert_eq!(
+            nb_neighboring_rolls(&grid, &Accessibility::default(), 2, 2),
+            2
+        );
+    }
+
+    #[test]
+    fn threshold() {
+        let grid = new_grid(indoc! {br"
+            @@.
+            @@.
+            ...
+        "});
+        let mut strict = Accessibility {
+            threshold: 3,
+            ..Accessibility::default()
+        };
+        assert_eq!(
+            remove_accessible_rolls(&mut grid.clone(), &strict, RemovalMode::InPlace).len(),
+            0
+        );
+        strict.threshold = 4;
+        assert_eq!(
+            remove_accessible_rolls(&mut grid.clone(), &strict, RemovalMode::InPlace).len(),
+            4
+        );
+    }
+
+    #[test]
+    fn other_neighborhoods() {
+        let grid = new_grid(indoc! {br"
+            @@@
+            @.@
+            @@@
+        "});
+        let von_neumann = Accessibility {
+            neighborhood: Neighborhood::von_neumann(),
+            ..Accessibility::default()
+        };
+        assert_eq!(nb_neighboring_rolls(&grid, &von_neumann, 0, 0), 2);
+        assert_eq!(nb_neighboring_rolls(&grid, &von_neumann, 1, 0), 2);
+        let padded = Accessibility {
+            boundary: Boundary::Pad(RollTile::Roll),
+            ..Accessibility::default()
+        };
+        assert_eq!(nb_neighboring_rolls(&grid, &padded, 0, 0), 7);
+        let wrapped = Accessibility {
+            boundary: Boundary::Wrap,
+            ..Accessibility::default()
+        };
+        assert_eq!(nb_neighboring_rolls(&grid, &wrapped, 0, 0), 7);
+    }
+
+    const SAMPLE: &[u8] = indoc! {br"
+        ..@@.@@@@.
+        @@@.@.@.@@
+        @@@@@.@.@@
+        @.@@@@..@.
+        @@.@@@@.@@
+        .@@@@@@@.@
+        .@.@.@.@@@
+        @.@@@.@@@@
+        .@@@@@@@@.
+        @.@.@@@.@.
+    "};
+
+    #[test]
+    fn solve_sample() {
+        let accessibility = Accessibility::default();
+        let passes = Some(Passes::default());
+        let mut grid = new_grid(SAMPLE);
+        assert_eq!(solve_for(&mut grid, &accessibility, passes, |_, _| {}), 43);
+        let mut remaining = new_grid(SAMPLE);
+        assert_eq!(
+            solve_for(&mut remaining, &accessibility, None, |_, _| {}),
+            43
+        );
+        assert_eq!(remaining, grid);
+    }
+
+    #[test]
+    fn automaton() {
+        let accessibility = Accessibility::default();
+        let rule = Rule::removal(DEFAULT_THRESHOLD, 8);
+        let mut grid = new_grid(SAMPLE);
+        let outcome = roll_automaton(rule, &accessibility).run(&mut grid, None);
+        // Last of the 9 passes removing rolls leads to the fixed point.
+        assert_eq!(
+            outcome,
+            Some(Cycle {
+                start: 9,
+                period: 1
+            })
+        );
+        let mut expected = new_grid(SAMPLE);
+        solve_for(&mut expected, &accessibility, None, |_, _| {});
+        assert_eq!(grid, expected);
+
+        let life = roll_automaton("B3/S23".parse().unwrap(), &accessibility);
+        let mut blinker = new_grid(b"...\n@@@\n...\n");
+        assert_eq!(
+            life.run(&mut blinker, None),
+            Some(Cycle {
+                start: 0,
+                period: 2
+            })
+        );
+    }
+
+    #[test]
+    fn passes() {
+        let accessibility = Accessibility::default();
+        let mut grid = new_grid(SAMPLE);
+        assert_eq!(
+            remove_rolls_by_scanning(&mut grid, &accessibility, &RemovalMode::Synchronous.into())
+                .nb_removed_per_generation(),
+            vec![13, 12, 7, 5, 2, 1, 1, 1, 1]
+        );
+        let mut grid = new_grid(SAMPLE);
+        assert_eq!(
+            remove_rolls_by_scanning(&mut grid, &accessibility, &RemovalMode::InPlace.into())
+                .nb_removed_per_generation(),
+            vec![30, 9, 4]
+        );
+        let mut reported = Vec::new();
+        let answer = solve_for(
+            &mut new_grid(SAMPLE),
+            &accessibility,
+            Some(RemovalMode::Synchronous.into()),
+            |pass, n| reported.push((pass, n)),
+        );
+        assert_eq!(answer, 43);
+        assert_eq!(reported[..2], [(1, 13), (2, 12)]);
+    }
+
+    #[test]
+    fn max_passes() {
+        let passes = Passes {
+            max_passes: Some(2),
+            ..RemovalMode::Synchronous.into()
+        };
+        let mut grid = new_grid(SAMPLE);
+        let timeline = remove_rolls_by_scanning(&mut grid, &Accessibility::default(), &passes);
+        assert_eq!(timeline.nb_removed_per_generation(), &[13, 12]);
+
+        // Resume from saved intermediate state.
+        let mut saved = Vec::new();
+        grid.save(&mut saved).unwrap();
+        let mut resumed = new_grid(&saved);
+        assert_eq!(resumed, grid);
+        assert_eq!(
+            solve_for(&mut resumed, &Accessibility::default(), None, |_, _| {}),
+            43 - 13 - 12
+        );
+    }
+
+    #[test]
+    fn checkpoints() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!(
+            "printing-department-{}.checkpoint",
+            std::process::id()
+        ));
+        let accessibility = Accessibility::default();
+        let passes = Passes {
+            max_passes: Some(3),
+            ..RemovalMode::Synchronous.into()
+        };
+        let fingerprint = Fingerprint::default().with(SAMPLE);
+        let mut checkpointer = Checkpointer::new(path.clone(), Duration::ZERO, false);
+        let mut state = checkpointer.start(fingerprint, || Ok(PassState::new(new_grid(SAMPLE))))?;
+        let answer = solve_with_checkpoints(
+            &mut state,
+            &accessibility,
+            &passes,
+            &mut checkpointer,
+            |_, _| {},
+        )?;
+        assert_eq!(answer, 13 + 12 + 7);
+
+        // Resume interrupted run.
+        let mut checkpointer = Checkpointer::new(path, Duration::ZERO, true);
+        let mut state: PassState = checkpointer.start(fingerprint, || unreachable!())?;
+        assert_eq!(state.nb_passes, 3);
+        let mut reported = Vec::new();
+        let answer = solve_with_checkpoints(
+            &mut state,
+            &accessibility,
+            &RemovalMode::Synchronous.into(),
+            &mut checkpointer,
+            |pass, n| reported.push((pass, n)),
+        )?;
+        checkpointer.finish()?;
+        assert_eq!(answer, 43);
+        assert_eq!(reported[0], (4, 5));
+        Ok(())
+    }
+
+    #[test]
+    fn neighbor_counts_per_generation() {
+        let accessibility = Accessibility::default();
+        let mut grid = new_grid(SAMPLE);
+        let timeline =
+            remove_rolls_by_scanning(&mut grid, &accessibility, &RemovalMode::Synchronous.into());
+        // Removed in generation 7, once down to 3 neighbors.
+        assert_eq!(timeline.removed_in(3, 2), Some(7));
+        assert_eq!(
+            nb_neighboring_rolls_per_generation(&timeline, &accessibility, 3, 2),
+            vec![7, 7, 6, 6, 5, 4, 3, 3, 2, 1]
+        );
+        // Remaining rolls keep enough neighbors.
+        for (x, y) in timeline.remaining_rolls() {
+            let counts = nb_neighboring_rolls_per_generation(&timeline, &accessibility, x, y);
+            assert!(counts[timeline.nb_generations()] >= accessibility.threshold);
+        }
+        assert_eq!(timeline.remaining_rolls().count(), grid_rolls(&grid));
+    }
+
+    fn grid_rolls(grid: &Grid) -> usize {
+        grid.positions()
+            .filter(|&(x, y)| grid.is_roll_at(x, y))
+            .count()
+    }
+
+    #[test]
+    fn sparse_grid() {
+        let accessibility = Accessibility::default();
+        let answer = solve_with_sparse_grid(Cursor::new(SAMPLE), &accessibility);
+        assert_eq!(answer.unwrap(), 43);
+        // Lone rolls far away are accessible.
+        let mut grid = SparseGrid::load(Cursor::new(SAMPLE)).unwrap();
+        grid.add_roll(-1_000_000, -1_000_000);
+        grid.add_roll(1_000_000, 7);
+        assert_eq!(
+            remove_rolls_from_layout(&mut grid, &accessibility.neighborhood, 4),
+            45
+        );
+        let padded = Accessibility {
+            boundary: Boundary::Pad(RollTile::Roll),
+            ..accessibility
+        };
+        assert!(solve_with_sparse_grid(Cursor::new(SAMPLE), &padded).is_err());
+    }
+
+    #[test]
+    fn parallel_passes() {
+        let accessibility = Accessibility::default();
+        for nb_jobs in [2, 3, 10, 20] {
+            let mut grid = new_grid(SAMPLE);
+            assert_eq!(
+                remove_rolls_by_scanning(
+                    &mut grid,
+                    &accessibility,
+                    &Passes {
+                        nb_jobs,
+                        ..RemovalMode::Synchronous.into()
+                    }
+                )
+                .nb_removed_per_generation(),
+                vec![13, 12, 7, 5, 2, 1, 1, 1, 1]
+            );
+        }
+    }
+
+    #[test]
+    fn synchronous_removal_uses_snapshot() {
+        // In place, removing the leftmost roll makes the next one accessible
+        // within the same pass, and so on.
+        let grid = new_grid(b"@@@@\n");
+        let accessibility = Accessibility {
+            neighborhood: Neighborhood::von_neumann(),
+            threshold: 2,
+            ..Accessibility::default()
+        };
+        let mut in_place = grid.clone();
+        assert_eq!(
+            remove_accessible_rolls(&mut in_place, &accessibility, RemovalMode::InPlace).len(),
+            4
+        );
+        let mut synchronous = grid;
+        assert_eq!(
+            remove_accessible_rolls(&mut synchronous, &accessibility, RemovalMode::Synchronous)
+                .len(),
+            2
+        );
+        assert_eq!(synchronous, new_grid(b".@@.\n"));
+    }
+
+    #[test]
+    fn parse_mode() {
+        assert_eq!(
+            "in-place".parse::<RemovalMode>().unwrap(),
+            RemovalMode::InPlace
+        );
+        assert_eq!(
+            "synchronous".parse::<RemovalMode>().unwrap(),
+            RemovalMode::Synchronous
+        );
+        assert!("lazy".parse::<RemovalMode>().is_err());
+    }
+
+    fn command_line(args: &[&str]) -> CommandLine {
+        CommandLine::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    const SAMPLE_PATH: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/bin/printing_department/sample.txt"
+    );
+
+    #[test]
+    fn save() -> anyhow::Result<()> {
+        let path =
+            std::env::temp_dir().join(format!("printing-department-{}.saved", std::process::id()));
+        let save = format!("--save={}", path.display());
+        let nb_rolls = RollLayout::rolls(&new_grid(SAMPLE)).len();
+        for args in [
+            vec![SAMPLE_PATH, &save],
+            vec![SAMPLE_PATH, "inspect", &save],
+            vec![SAMPLE_PATH, "--annotate", &save],
+        ] {
+            run(&command_line(&args))?;
+            let saved = Grid::load(Cursor::new(std::fs::read(&path)?))?;
+            assert_eq!(RollLayout::rolls(&saved).len(), nb_rolls - 43, "{:?}", args);
+            std::fs::remove_file(&path)?;
+        }
+        assert!(run(&command_line(&[SAMPLE_PATH, "--bitset", &save])).is_err());
+        assert!(run(&command_line(&[SAMPLE_PATH, "--sparse", &save])).is_err());
+        assert!(!path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn clusters() -> anyhow::Result<()> {
+        for args in [
+            vec![SAMPLE_PATH, "--clusters"],
+            vec![SAMPLE_PATH, "inspect", "--clusters"],
+            vec![SAMPLE_PATH, "--annotate", "--clusters"],
+        ] {
+            run(&command_line(&args))?;
+        }
+        for option in ["--bitset", "--sparse", "--rule=B3/S23"] {
+            let error = run(&command_line(&[SAMPLE_PATH, option, "--clusters"])).unwrap_err();
+            assert!(error.to_string().contains("--clusters"), "{}", error);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn sparse_rejects_passes() -> anyhow::Result<()> {
+        run(&command_line(&[SAMPLE_PATH, "--sparse"]))?;
+        for option in ["--scan", "--mode=synchronous", "--jobs=2", "--max-passes=1"] {
+            assert!(run(&command_line(&[SAMPLE_PATH, "--sparse", option])).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn passes_from_command_line() -> anyhow::Result<()> {
+        let generations =
+            |args: &[&str]| Passes::generations_from_command_line(&command_line(args));
+        assert_eq!(generations(&[])?, RemovalMode::Synchronous.into());
+        assert_eq!(
+            generations(&["--max-passes=2"])?.mode,
+            RemovalMode::Synchronous
+        );
+        assert_eq!(
+            generations(&["--mode=in-place"])?.mode,
+            RemovalMode::InPlace
+        );
+        assert!(generations(&["--mode=in-place", "--jobs=2"]).is_err());
+
+        let scan =
+            |args: &[&str]| Passes::from_command_line(&command_line(args), RemovalMode::InPlace);
+        assert_eq!(scan(&[])?, None);
+        assert_eq!(scan(&["--scan"])?, Some(RemovalMode::InPlace.into()));
+        assert_eq!(
+            scan(&["--jobs=3"])?.map(|p| p.mode),
+            Some(RemovalMode::Synchronous)
+        );
+        Ok(())
+    }
+
+    fn arb_mode() -> impl Strategy<Value = RemovalMode> {
+        prop_oneof![Just(RemovalMode::InPlace), Just(RemovalMode::Synchronous)]
+    }
+
+    fn arb_grid_of_size(width: usize, height: usize) -> impl Strategy<Value = Grid> {
+        prop::collection::vec(prop::bool::ANY, width * height).prop_map(move |tiles| {
+            let mut grid = Grid::new(width, height, RollTile::Empty);
+            for (i, roll) in tiles.into_iter().enumerate() {
+                if roll {
+                    grid.set(i % width, i / width, RollTile::Roll);
+                }
+            }
+            grid
+        })
+    }
+
+    fn arb_grid() -> impl Strategy<Value = Grid> {
+        (1..12_usize, 1..12_usize).prop_flat_map(|(width, height)| arb_grid_of_size(width, height))
+    }
+
+    fn arb_accessibility() -> impl Strategy<Value = Accessibility> {
+        let neighborhood = prop_oneof![
+            Just(Neighborhood::von_neumann()),
+            Just(Neighborhood::moore()),
+            Just(Neighborhood::radius(2)),
+            Just(Neighborhood::hexagonal()),
+            Just(Neighborhood::custom(vec![(1, 0), (2, 1), (0, -3)])),
+        ];
+        let boundary = prop_oneof![
+            Just(Boundary::Clip),
+            Just(Boundary::Wrap),
+            Just(Boundary::Pad(RollTile::Roll)),
+        ];
+        (neighborhood, boundary, 0..10_usize).prop_map(|(neighborhood, boundary, threshold)| {
+            Accessibility {
+                neighborhood,
+                boundary,
+                threshold,
+            }
+        })
+    }
+
+    /// Removes one accessible roll at a time, scanning whole grid again after
+    /// each removal.
+    fn remove_rolls_naively(grid: &mut Grid, accessibility: &Accessibility) -> usize {
+        let mut nb_removed_rolls = 0;
+        while let Some((x, y)) = grid.positions().find(|&(x, y)| {
+            grid.is_roll_at(x, y)
+                && nb_neighboring_rolls(grid, accessibility, x, y) < accessibility.threshold
+        }) {
+            grid.remove_roll_at(x, y);
+            nb_removed_rolls += 1;
+        }
+        nb_removed_rolls
+    }
+
+    #[test]
+    fn bitset() {
+        let accessibility = Accessibility::default();
+        let mut passes = Vec::new();
+        let synchronous = RemovalMode::Synchronous.into();
+        let answer =
+            solve_with_bitset(Cursor::new(SAMPLE), &accessibility, &synchronous, |_, n| {
+                passes.push(n)
+            });
+        assert_eq!(answer.unwrap(), 43);
+        assert_eq!(passes, vec![13, 12, 7, 5, 2, 1, 1, 1, 1]);
+
+        let first_pass = Passes {
+            max_passes: Some(1),
+            ..synchronous
+        };
+        let answer = solve_with_bitset(Cursor::new(SAMPLE), &accessibility, &first_pass, |_, _| {});
+        assert_eq!(answer.unwrap(), 13);
+
+        let wrapped = Accessibility {
+            boundary: Boundary::Wrap,
+            ..accessibility.clone()
+        };
+        assert!(solve_with_bitset(Cursor::new(SAMPLE), &wrapped, &synchronous, |_, _| {}).is_err());
+        let in_place = RemovalMode::InPlace.into();
+        assert!(
+            solve_with_bitset(Cursor::new(SAMPLE), &accessibility, &in_place, |_, _| {}).is_err()
+        );
+        let parallel = Passes {
+            nb_jobs: 2,
+            ..synchronous
+        };
+        assert!(
+            solve_with_bitset(Cursor::new(SAMPLE), &accessibility, &parallel, |_, _| {}).is_err()
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn incremental_removal_matches_scanning(
+            grid in arb_grid(),
+            accessibility in arb_accessibility(),
+            mode in arb_mode(),
+        ) {
+            let mut scanned = grid.clone();
+            let mut incremental = grid;
+            prop_assert_eq!(
+                remove_rolls_incrementally(&mut incremental, &accessibility),
+                remove_rolls_by_scanning(&mut scanned, &accessibility, &mode.into())
+                    .nb_removed_per_generation()
+                    .iter()
+                    .sum::<usize>()
+            );
+            prop_assert_eq!(incremental, scanned);
+        }
+
+        #[test]
+        fn bitset_matches_byte_grid(
+            // Wide enough to span several words.
+            grid in (1..150_usize, 1..8_usize).prop_flat_map(|(width, height)| arb_grid_of_size(width, height)),
+            threshold in 0..10_usize,
+        ) {
+            let accessibility = Accessibility { threshold, ..Accessibility::default() };
+            let mut bits = BitGrid::from_grid(&grid);
+            let mut bytes = grid;
+            let timeline = remove_rolls_by_scanning(&mut bytes, &accessibility, &RemovalMode::Synchronous.into());
+            for &n in timeline.nb_removed_per_generation() {
+                prop_assert_eq!(bits.remove_accessible_rolls(threshold), n);
+            }
+            prop_assert_eq!(bits.remove_accessible_rolls(threshold), 0);
+            prop_assert_eq!(bits.to_grid(), bytes);
+        }
+
+        #[test]
+        fn parallel_matches_sequential(
+            grid in arb_grid(),
+            accessibility in arb_accessibility(),
+            nb_jobs in 2..6_usize,
+        ) {
+            let mut sequential = grid.clone();
+            let mut parallel = grid;
+            prop_assert_eq!(
+                remove_accessible_rolls_in_parallel(&mut parallel, &accessibility, nb_jobs),
+                remove_accessible_rolls(&mut sequential, &accessibility, RemovalMode::Synchronous)
+            );
+            prop_assert_eq!(parallel, sequential);
+        }
+
+        #[test]
+        fn neighbor_counts_match_grid(
+            grid in arb_grid(),
+            accessibility in arb_accessibility(),
+        ) {
+            let mut last = grid.clone();
+            let timeline = remove_rolls_by_scanning(&mut last, &accessibility, &RemovalMode::Synchronous.into());
+            let (x, y) = (grid.width() / 2, grid.height() / 2);
+            let counts = nb_neighboring_rolls_per_generation(&timeline, &accessibility, x, y);
+            for (generation, count) in counts.into_iter().enumerate() {
+                let mut after = grid.clone();
+                for (px, py) in grid.positions() {
+                    if !timeline.is_roll_after(px, py, generation) {
+                        after.set(px, py, RollTile::Empty);
+                    }
+                }
+                let expected = after
+                    .neighborhood(x, y, &accessibility.neighborhood, &accessibility.boundary)
+                    .filter(|tile| **tile == RollTile::Roll)
+                    .count();
+                prop_assert_eq!(count, expected);
+            }
+        }
+
+        #[test]
+        fn layouts_match_grid(
+            grid in arb_grid(),
+            accessibility in arb_accessibility(),
+        ) {
+            let accessibility = Accessibility { boundary: Boundary::Clip, ..accessibility };
+            let mut sparse = SparseGrid::new();
+            for (x, y) in RollLayout::rolls(&grid) {
+                sparse.add_roll(x, y);
+            }
+            let mut dense = grid.clone();
+            let mut incremental = grid;
+            let expected = remove_rolls_incrementally(&mut incremental, &accessibility);
+            let (neighborhood, threshold) = (&accessibility.neighborhood, accessibility.threshold);
+            prop_assert_eq!(remove_rolls_from_layout(&mut dense, neighborhood, threshold), expected);
+            prop_assert_eq!(remove_rolls_from_layout(&mut sparse, neighborhood, threshold), expected);
+            let mut remaining = sparse.rolls();
+            remaining.sort_by_key(|&(x, y)| (y, x));
+            prop_assert_eq!(&remaining, &RollLayout::rolls(&incremental));
+            prop_assert_eq!(dense, incremental);
+        }
+
+        #[test]
+        fn automaton_matches_synchronous_removal(
+            grid in arb_grid(),
+            accessibility in arb_accessibility(),
+        ) {
+            let rule = Rule::removal(
+                accessibility.threshold,
+                accessibility.neighborhood.offsets().len(),
+            );
+            let mut expected = grid.clone();
+            let timeline = remove_rolls_by_scanning(
+                &mut expected,
+                &accessibility,
+                &RemovalMode::Synchronous.into(),
+            );
+            let mut grid = grid;
+            let outcome = roll_automaton(rule, &accessibility).run(&mut grid, None);
+            let nb_passes = timeline.nb_removed_per_generation().len();
+            prop_assert_eq!(outcome, Some(Cycle { start: nb_passes, period: 1 }));
+            prop_assert_eq!(grid, expected);
+        }
+
+        #[test]
+        fn fixed_point_matches_naive_rescan(
+            grid in arb_grid(),
+            accessibility in arb_accessibility(),
+        ) {
+            let mut expected = grid.clone();
+            let nb_removed_rolls = remove_rolls_naively(&mut expected, &accessibility);
+            let modes = [None, Some(RemovalMode::InPlace), Some(RemovalMode::Synchronous)];
+            for mode in modes {
+                let mut removed = grid.clone();
+                let passes = mode.map(Passes::from);
+                prop_assert_eq!(
+                    solve_for(&mut removed, &accessibility, passes, |_, _| {}),
+                    nb_removed_rolls
+                );
+                prop_assert_eq!(&removed, &expected);
+            }
+        }
     }
 }
@@ -0,0 +1,83 @@
+use std::collections::HashSet;
+use std::io::BufRead;
+
+use advent_code_25::grid::parse_row;
+
+use crate::grid::{RollLayout, RollTile};
+
+/// Rolls at arbitrary signed coordinates, storing only tiles with a roll.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SparseGrid {
+    rolls: HashSet<(isize, isize)>,
+}
+
+impl SparseGrid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads rolls from lines of tiles with first tile of first line at
+    /// `(0, 0)`.  Unlike `Grid::load`, lines may have any length.
+    pub fn load<R: BufRead>(input: R) -> anyhow::Result<Self> {
+        let mut grid = Self::new();
+        for (y, line) in input.split(b'\n').enumerate() {
+            let row: Vec<RollTile> = parse_row(&line?, y + 1)?;
+            for (x, tile) in row.into_iter().enumerate() {
+                if tile == RollTile::Roll {
+                    grid.add_roll(x as isize, y as isize);
+                }
+            }
+        }
+        Ok(grid)
+    }
+
+    pub fn add_roll(&mut self, x: isize, y: isize) {
+        self.rolls.insert((x, y));
+    }
+}
+
+impl RollLayout for SparseGrid {
+    fn has_roll(&self, x: isize, y: isize) -> bool {
+        self.rolls.contains(&(x, y))
+    }
+
+    fn take_roll(&mut self, x: isize, y: isize) {
+        let removed = self.rolls.remove(&(x, y));
+        debug_assert!(removed);
+    }
+
+    fn rolls(&self) -> Vec<(isize, isize)> {
+        self.rolls.iter().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn load_ragged_lines() {
+        let grid = SparseGrid::load(Cursor::new(b"@\n..@@\n\n.@")).unwrap();
+        assert_eq!(grid.rolls().len(), 4);
+        assert!(grid.has_roll(3, 1));
+        assert!(grid.has_roll(1, 3));
+        assert!(!grid.has_roll(1, 1));
+        let e = SparseGrid::load(Cursor::new(b"@\n.x")).unwrap_err();
+        assert_eq!(e.to_string(), "line 2, column 2: unexpected tile: 'x'");
+        let e = SparseGrid::load(Cursor::new("@\n.é.x".as_bytes())).unwrap_err();
+        assert_eq!(e.to_string(), "line 2, column 2: unexpected tile: 'é'");
+        let e = SparseGrid::load(Cursor::new(b"@\n.\xff")).unwrap_err();
+        assert_eq!(e.to_string(), "line 2, column 2: invalid UTF-8");
+    }
+
+    #[test]
+    fn negative_coordinates() {
+        let mut grid = SparseGrid::new();
+        grid.add_roll(-1_000_000, 5);
+        grid.add_roll(3, -7);
+        assert!(grid.has_roll(-1_000_000, 5));
+        grid.take_roll(3, -7);
+        assert_eq!(grid.rolls(), vec![(-1_000_000, 5)]);
+    }
+}
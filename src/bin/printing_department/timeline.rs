@@ -0,0 +1,374 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use advent_code_25::grid::{self, Tile};
+
+use crate::grid::{Grid, RollGridExt};
+
+/// State of a tile in one frame of a timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameTile {
+    Empty,
+    Roll,
+    /// Roll removed during the generation shown by the frame.
+    Removed,
+}
+
+impl FrameTile {
+    /// Returns color of tile in images.
+    fn rgb(&self) -> [u8; 3] {
+        match self {
+            Self::Empty => [255, 255, 255],
+            Self::Roll => [64, 64, 64],
+            Self::Removed => [220, 40, 40],
+        }
+    }
+}
+
+impl Tile for FrameTile {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' => Some(Self::Empty),
+            '@' => Some(Self::Roll),
+            'x' => Some(Self::Removed),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        match self {
+            Self::Empty => '.',
+            Self::Roll => '@',
+            Self::Removed => 'x',
+        }
+    }
+}
+
+/// Format of frames written as images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageFormat {
+    #[default]
+    Ppm,
+    Png,
+}
+
+impl ImageFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Ppm => "ppm",
+            Self::Png => "png",
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ppm" => Ok(Self::Ppm),
+            "png" => Ok(Self::Png),
+            _ => anyhow::bail!("unknown image format: {} (expected ppm or png)", s),
+        }
+    }
+}
+
+/// Record of the generation (pass over the grid, starting at 1) in which each
+/// roll was removed.
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    initial: Grid,
+    removed_in: grid::Grid<Option<usize>>,
+    nb_removed_per_generation: Vec<usize>,
+}
+
+impl Timeline {
+    /// Creates empty timeline starting from `initial` grid.
+    pub fn new(initial: Grid) -> Self {
+        let removed_in = grid::Grid::new(initial.width(), initial.height(), None);
+        Self {
+            initial,
+            removed_in,
+            nb_removed_per_generation: Vec::new(),
+        }
+    }
+
+    /// Records next generation, which removed rolls at `removed` positions.
+    pub fn record_generation(&mut self, removed: &[(usize, usize)]) {
+        let generation = self.nb_removed_per_generation.len() + 1;
+        for &(x, y) in removed {
+            debug_assert!(self.initial.is_roll_at(x, y) && self.removed_in[(x, y)].is_none());
+            self.removed_in[(x, y)] = Some(generation);
+        }
+        self.nb_removed_per_generation.push(removed.len());
+    }
+
+    /// Returns grid before first generation.
+    pub fn initial(&self) -> &Grid {
+        &self.initial
+    }
+
+    pub fn nb_generations(&self) -> usize {
+        self.nb_removed_per_generation.len()
+    }
+
+    /// Returns number of rolls removed by each generation.
+    pub fn nb_removed_per_generation(&self) -> &[usize] {
+        &self.nb_removed_per_generation
+    }
+
+    /// Returns generation in which roll at `(x, y)` was removed, or `None` if
+    /// there was no roll or it was never removed.
+    pub fn removed_in(&self, x: usize, y: usize) -> Option<usize> {
+        self.removed_in[(x, y)]
+    }
+
+    /// Returns true if there is a roll at `(x, y)` once `generation` is over,
+    /// 0 being the initial grid.
+    pub fn is_roll_after(&self, x: usize, y: usize, generation: usize) -> bool {
+        self.initial.is_roll_at(x, y) && self.removed_in(x, y).is_none_or(|g| g > generation)
+    }
+
+    /// Returns iterator over positions of rolls never removed, in row-major
+    /// order.
+    pub fn remaining_rolls(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.initial
+            .positions()
+            .filter(|&(x, y)| self.initial.is_roll_at(x, y) && self.removed_in(x, y).is_none())
+    }
+
+    /// Returns grid as it was during `generation`, 0 being the initial grid.
+    pub fn frame(&self, generation: usize) -> grid::Grid<FrameTile> {
+        let mut frame = grid::Grid::new(
+            self.initial.width(),
+            self.initial.height(),
+            FrameTile::Empty,
+        );
+        for (x, y) in self.initial.positions() {
+            if self.initial.is_roll_at(x, y) {
+                frame[(x, y)] = match self.removed_in(x, y) {
+                    Some(g) if g < generation => FrameTile::Empty,
+                    Some(g) if g == generation => FrameTile::Removed,
+                    _ => FrameTile::Roll,
+                };
+            }
+        }
+        frame
+    }
+
+    /// Prints initial grid with each removed roll replaced by the generation
+    /// it was removed in.  Rolls never removed are shown as `@`.
+    pub fn write_annotated<W: Write>(&self, mut output: W) -> anyhow::Result<()> {
+        let cell_width = self.nb_generations().to_string().len();
+        for y in 0..self.initial.height() {
+            let cells: Vec<String> = (0..self.initial.width())
+                .map(|x| {
+                    let cell = match self.removed_in(x, y) {
+                        Some(generation) => generation.to_string(),
+                        None => self.initial[(x, y)].to_char().to_string(),
+                    };
+                    format!("{:>width$}", cell, width = cell_width)
+                })
+                .collect();
+            writeln!(output, "{}", cells.join(" "))?;
+        }
+        Ok(())
+    }
+
+    /// Plays all frames on an ANSI terminal, waiting `delay` between frames.
+    pub fn animate<W: Write>(&self, mut output: W, delay: Duration) -> anyhow::Result<()> {
+        for generation in 0..=self.nb_generations() {
+            // Clear screen and move cursor to top-left corner.
+            write!(output, "\x1b[2J\x1b[H")?;
+            let frame = self.frame(generation);
+            for row in frame.rows() {
+                for tile in row {
+                    match tile {
+                        FrameTile::Removed => write!(output, "\x1b[1;31mx\x1b[0m")?,
+                        _ => write!(output, "{}", tile.to_char())?,
+                    }
+                }
+                writeln!(output)?;
+            }
+            writeln!(
+                output,
+                "generation {}/{}: {} rolls removed",
+                generation,
+                self.nb_generations(),
+                generation
+                    .checked_sub(1)
+                    .map_or(0, |g| self.nb_removed_per_generation[g])
+            )?;
+            output.flush()?;
+            if generation < self.nb_generations() {
+                thread::sleep(delay);
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes every frame to `directory` as `frame-<generation>.<ext>` images
+    /// with tiles of `scale` by `scale` pixels.
+    pub fn write_frames(
+        &self,
+        directory: &Path,
+        format: ImageFormat,
+        scale: usize,
+    ) -> anyhow::Result<()> {
+        fs::create_dir_all(directory)
+            .map_err(|e| anyhow::anyhow!("failed to create {}: {}", directory.display(), e))?;
+        for generation in 0..=self.nb_generations() {
+            let path = directory.join(format!("frame-{:04}.{}", generation, format.extension()));
+            let file = File::create(&path)
+                .map_err(|e| anyhow::anyhow!("failed to create {}: {}", path.display(), e))?;
+            write_image(BufWriter::new(file), &self.frame(generation), format, scale)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `frame` as an image with tiles of `scale` by `scale` pixels.
+pub fn write_image<W: Write>(
+    mut output: W,
+    frame: &grid::Grid<FrameTile>,
+    format: ImageFormat,
+    scale: usize,
+) -> anyhow::Result<()> {
+    if scale == 0 {
+        anyhow::bail!("invalid scale: 0");
+    }
+    let width = frame.width() * scale;
+    let height = frame.height() * scale;
+    let mut pixels = Vec::with_capacity(width * height * 3);
+    for row in frame.rows() {
+        let line: Vec<u8> = row
+            .iter()
+            .flat_map(|tile| std::iter::repeat_n(tile.rgb(), scale).flatten())
+            .collect();
+        for _ in 0..scale {
+            pixels.extend_from_slice(&line);
+        }
+    }
+
+    match format {
+        ImageFormat::Ppm => {
+            write!(output, "P6\n{} {}\n255\n", width, height)?;
+            output.write_all(&pixels)?;
+        }
+        ImageFormat::Png => {
+            let too_large = || anyhow::anyhow!("image too large: {}x{}", width, height);
+            let mut encoder = png::Encoder::new(
+                &mut output,
+                width.try_into().map_err(|_| too_large())?,
+                height.try_into().map_err(|_| too_large())?,
+            );
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(&pixels)?;
+            writer.finish()?;
+        }
+    }
+    output.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use std::io::Cursor;
+
+    /// Timeline of a 3x2 grid whose corners are removed first.
+    fn timeline() -> Timeline {
+        let grid = Grid::load(Cursor::new(b"@@@\n@.@\n")).unwrap();
+        let mut timeline = Timeline::new(grid);
+        timeline.record_generation(&[(0, 0), (2, 0)]);
+        timeline.record_generation(&[(1, 0)]);
+        timeline
+    }
+
+    fn frame_text(frame: &grid::Grid<FrameTile>) -> Vec<String> {
+        frame
+            .rows()
+            .map(|row| row.iter().map(Tile::to_char).collect())
+            .collect()
+    }
+
+    #[test]
+    fn generations() {
+        let timeline = timeline();
+        assert_eq!(timeline.nb_generations(), 2);
+        assert_eq!(timeline.nb_removed_per_generation(), &[2, 1]);
+        assert_eq!(timeline.removed_in(0, 0), Some(1));
+        assert_eq!(timeline.removed_in(1, 0), Some(2));
+        assert_eq!(timeline.removed_in(0, 1), None);
+        assert_eq!(timeline.removed_in(1, 1), None);
+    }
+
+    #[test]
+    fn queries() {
+        let timeline = timeline();
+        assert!(timeline.is_roll_after(1, 0, 1));
+        assert!(!timeline.is_roll_after(1, 0, 2));
+        assert!(!timeline.is_roll_after(1, 1, 0));
+        assert!(timeline.is_roll_after(0, 1, 2));
+        let remaining: Vec<_> = timeline.remaining_rolls().collect();
+        assert_eq!(remaining, vec![(0, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn frames() {
+        let timeline = timeline();
+        assert_eq!(frame_text(&timeline.frame(0)), vec!["@@@", "@.@"]);
+        assert_eq!(frame_text(&timeline.frame(1)), vec!["x@x", "@.@"]);
+        assert_eq!(frame_text(&timeline.frame(2)), vec![".x.", "@.@"]);
+    }
+
+    #[test]
+    fn annotated() {
+        let mut output = Vec::new();
+        timeline().write_annotated(&mut output).unwrap();
+        let expected = indoc! {"
+            1 2 1
+            @ . @
+        "};
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn animation() {
+        let mut output = Vec::new();
+        timeline().animate(&mut output, Duration::ZERO).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("\x1b[2J").count(), 3);
+        assert!(output.contains("\x1b[1;31mx\x1b[0m@\x1b[1;31mx\x1b[0m\n"));
+        assert!(output.ends_with("generation 2/2: 1 rolls removed\n"));
+    }
+
+    #[test]
+    fn ppm() {
+        let mut output = Vec::new();
+        write_image(&mut output, &timeline().frame(2), ImageFormat::Ppm, 2).unwrap();
+        let header = b"P6\n6 4\n255\n";
+        assert!(output.starts_with(header));
+        let pixels = &output[header.len()..];
+        assert_eq!(pixels.len(), 6 * 4 * 3);
+        // Second tile of first row, removed in generation 2.
+        assert_eq!(pixels[6..9], FrameTile::Removed.rgb());
+        assert_eq!(pixels[18 + 6..18 + 9], FrameTile::Removed.rgb());
+        assert_eq!(pixels[0..3], FrameTile::Empty.rgb());
+    }
+
+    #[test]
+    fn png() {
+        let mut output = Vec::new();
+        write_image(&mut output, &timeline().frame(0), ImageFormat::Png, 3).unwrap();
+        assert!(output.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert!(write_image(Vec::new(), &timeline().frame(0), ImageFormat::Png, 0).is_err());
+    }
+}
@@ -1,119 +1,293 @@
-const MAX_DIAL: usize = 100;
+/// Number of positions of the dial used in the puzzle.
+pub const DEFAULT_SIZE: i64 = 100;
 
-/// A round dial graduated from 0 to 99 clock-wise.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A round dial graduated from 0 to `size - 1` clock-wise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Dial {
-    pos: usize,
+    pos: i64,
+    size: i64,
+}
+
+/// What happened while turning a dial.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RotationOutcome {
+    /// Number of times the dial pointed at zero while being turned, including
+    /// when it stopped on zero.
+    pub nb_zero_passes: u64,
+    /// True if the dial stopped on zero.
+    pub landed_on_zero: bool,
+    /// Position the dial stopped on.
+    pub position: i64,
 }
 
 impl Dial {
-    pub fn new(pos: usize) -> Self {
-        debug_assert!(pos < MAX_DIAL);
-        Self { pos }
+    /// Creates a dial with `size` positions pointing at `pos`.
+    pub fn new(size: i64, pos: i64) -> anyhow::Result<Self> {
+        if size <= 0 {
+            anyhow::bail!("dial must have at least one position");
+        }
+        if !(0..size).contains(&pos) {
+            anyhow::bail!("dial position {} out of range: must be in 0..{}", pos, size);
+        }
+        Ok(Self { pos, size })
     }
 
-    pub fn position(&self) -> usize {
+    pub fn position(&self) -> i64 {
         self.pos
     }
 
-    /// Turns dial `n` clicks to the right and returns number of times dial pointed at zero
-    /// while being turned.
-    pub fn turn_right(&mut self, n: usize) -> usize {
-        let nb_dial_on_zero = (self.pos + n) / MAX_DIAL;
-        self.pos = (self.pos + n) % MAX_DIAL;
-        debug_assert!(self.pos < MAX_DIAL);
-        nb_dial_on_zero
+    pub fn size(&self) -> i64 {
+        self.size
     }
 
-    /// Turns dial `n` clicks to the left and returns number of times dial pointed at zero
-    /// while being turned.
-    pub fn turn_left(&mut self, n: usize) -> usize {
-        // To avoid complications induced by negative numbers, create a mirror
-        // dial, turn it right instead, and mirror resulting position back.
-        let mut mirror_dial = Dial::new(Self::mirror_position(self.pos));
-        let nb_dial_on_zero = mirror_dial.turn_right(n);
-        self.pos = Self::mirror_position(mirror_dial.position());
-        debug_assert!(self.pos < MAX_DIAL);
-        nb_dial_on_zero
+    /// Returns number of clicks needed to point at zero when turning right if
+    /// `delta` is positive or left if negative.
+    pub fn clicks_to_zero(&self, delta: i64) -> i64 {
+        if delta >= 0 {
+            self.size - self.pos
+        } else if self.pos == 0 {
+            self.size
+        } else {
+            self.pos
+        }
     }
 
-    /// Returns dial position `pos` in a mirror dial.
-    fn mirror_position(pos: usize) -> usize {
-        if pos == 0 { 0 } else { MAX_DIAL - pos }
+    /// Turns dial `delta` clicks to the right if positive or to the left if
+    /// negative.
+    pub fn rotate(&mut self, delta: i64) -> RotationOutcome {
+        let dist_to_zero = self.clicks_to_zero(delta);
+        let n = delta.unsigned_abs();
+        let nb_zero_passes = if n < dist_to_zero as u64 {
+            0
+        } else {
+            1 + (n - dist_to_zero as u64) / self.size as u64
+        };
+        // Widen to avoid overflow when turning by huge amounts.
+        self.pos = (self.pos as i128 + delta as i128).rem_euclid(self.size as i128) as i64;
+        RotationOutcome {
+            nb_zero_passes,
+            landed_on_zero: self.pos == 0 && n > 0,
+            position: self.pos,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    fn dial(pos: i64) -> Dial {
+        Dial::new(DEFAULT_SIZE, pos).unwrap()
+    }
 
     #[test]
     fn right_rotation_no_wrap() {
-        let mut d = Dial::new(10);
-        assert_eq!(d.turn_right(5), 0);
+        let mut d = dial(10);
+        assert_eq!(d.rotate(5).nb_zero_passes, 0);
         assert_eq!(d.position(), 15);
     }
 
     #[test]
     fn right_rotation_wrap_once() {
-        let mut d = Dial::new(60);
-        assert_eq!(d.turn_right(60), 1);
+        let mut d = dial(60);
+        assert_eq!(d.rotate(60).nb_zero_passes, 1);
         assert_eq!(d.position(), 20);
     }
 
     #[test]
     fn right_rotation_wrap_many() {
-        let mut d = Dial::new(60);
-        assert_eq!(d.turn_right(260), 3);
+        let mut d = dial(60);
+        assert_eq!(d.rotate(260).nb_zero_passes, 3);
         assert_eq!(d.position(), 20);
     }
 
     #[test]
     fn right_rotation_end_on_zero() {
-        let mut d = Dial::new(1);
-        assert_eq!(d.turn_right(99), 1);
+        let mut d = dial(1);
+        assert_eq!(d.rotate(99).nb_zero_passes, 1);
         assert_eq!(d.position(), 0);
     }
 
     #[test]
     fn right_rotation_wrap_and_end_on_zero() {
-        let mut d = Dial::new(0);
-        assert_eq!(d.turn_right(200), 2);
+        let mut d = dial(0);
+        assert_eq!(d.rotate(200).nb_zero_passes, 2);
         assert_eq!(d.position(), 0);
     }
 
     #[test]
     fn left_rotation_no_wrap() {
-        let mut d = Dial::new(20);
-        assert_eq!(d.turn_left(5), 0);
+        let mut d = dial(20);
+        assert_eq!(d.rotate(-5).nb_zero_passes, 0);
         assert_eq!(d.position(), 15);
     }
 
     #[test]
     fn left_rotation_end_on_zero() {
-        let mut d = Dial::new(10);
-        assert_eq!(d.turn_left(10), 1);
+        let mut d = dial(10);
+        assert_eq!(d.rotate(-10).nb_zero_passes, 1);
         assert_eq!(d.position(), 0);
     }
 
     #[test]
     fn left_rotation_wrap_once() {
-        let mut d = Dial::new(10);
-        assert_eq!(d.turn_left(30), 1);
+        let mut d = dial(10);
+        assert_eq!(d.rotate(-30).nb_zero_passes, 1);
         assert_eq!(d.position(), 80);
     }
 
     #[test]
     fn left_rotation_wrap_many() {
-        let mut d = Dial::new(10);
-        assert_eq!(d.turn_left(130), 2);
+        let mut d = dial(10);
+        assert_eq!(d.rotate(-130).nb_zero_passes, 2);
         assert_eq!(d.position(), 80);
     }
 
     #[test]
     fn left_rotation_wrap_many_end_on_zero() {
-        let mut d = Dial::new(49);
-        assert_eq!(d.turn_left(849), 9);
+        let mut d = dial(49);
+        assert_eq!(d.rotate(-849).nb_zero_passes, 9);
         assert_eq!(d.position(), 0);
     }
+
+    #[test]
+    fn new_rejects_out_of_range_position() {
+        assert!(Dial::new(100, 99).is_ok());
+        assert!(Dial::new(100, 100).is_err());
+        assert!(Dial::new(0, 0).is_err());
+    }
+
+    #[test]
+    fn small_dial_right_rotation() {
+        let mut d = Dial::new(10, 5).unwrap();
+        assert_eq!(d.rotate(27).nb_zero_passes, 3);
+        assert_eq!(d.position(), 2);
+    }
+
+    #[test]
+    fn small_dial_left_rotation() {
+        let mut d = Dial::new(10, 5).unwrap();
+        assert_eq!(d.rotate(-27).nb_zero_passes, 3);
+        assert_eq!(d.position(), 8);
+    }
+
+    #[test]
+    fn rotate_positive_is_right() {
+        let mut d = dial(60);
+        assert_eq!(
+            d.rotate(260),
+            RotationOutcome {
+                nb_zero_passes: 3,
+                landed_on_zero: false,
+                position: 20,
+            }
+        );
+    }
+
+    #[test]
+    fn rotate_negative_is_left() {
+        let mut d = dial(49);
+        assert_eq!(
+            d.rotate(-849),
+            RotationOutcome {
+                nb_zero_passes: 9,
+                landed_on_zero: true,
+                position: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn rotate_from_zero() {
+        let mut d = dial(0);
+        assert_eq!(d.rotate(-1).nb_zero_passes, 0);
+        assert_eq!(d.position(), 99);
+        let mut d = dial(0);
+        assert_eq!(d.rotate(-100).nb_zero_passes, 1);
+        assert_eq!(d.position(), 0);
+    }
+
+    #[test]
+    fn rotate_zero_clicks() {
+        let mut d = dial(0);
+        assert_eq!(
+            d.rotate(0),
+            RotationOutcome {
+                nb_zero_passes: 0,
+                landed_on_zero: false,
+                position: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn rotate_huge_right() {
+        let mut d = dial(50);
+        assert_eq!(
+            d.rotate(i64::MAX),
+            RotationOutcome {
+                nb_zero_passes: 92233720368547758,
+                landed_on_zero: false,
+                position: 57,
+            }
+        );
+    }
+
+    #[test]
+    fn rotate_huge_left() {
+        let mut d = dial(50);
+        assert_eq!(
+            d.rotate(i64::MIN),
+            RotationOutcome {
+                nb_zero_passes: 92233720368547758,
+                landed_on_zero: false,
+                position: 42,
+            }
+        );
+    }
+
+    #[test]
+    fn rotate_huge_dial() {
+        let mut d = Dial::new(i64::MAX, i64::MAX - 1).unwrap();
+        assert_eq!(d.rotate(i64::MAX).nb_zero_passes, 1);
+        assert_eq!(d.position(), i64::MAX - 1);
+        assert_eq!(d.rotate(i64::MIN).nb_zero_passes, 1);
+        assert_eq!(d.position(), i64::MAX - 2);
+    }
+
+    /// Turns dial one click at a time, returning final position and number of
+    /// times it pointed at zero.
+    fn turn_click_by_click(size: i64, pos: i64, delta: i64) -> (i64, u64) {
+        let mut pos = pos;
+        let mut nb_zero_passes = 0;
+        for _ in 0..delta.unsigned_abs() {
+            pos = (pos + delta.signum()).rem_euclid(size);
+            if pos == 0 {
+                nb_zero_passes += 1;
+            }
+        }
+        (pos, nb_zero_passes)
+    }
+
+    fn arb_dial() -> impl Strategy<Value = Dial> {
+        (1..30_i64)
+            .prop_flat_map(|size| (0..size).prop_map(move |pos| Dial::new(size, pos).unwrap()))
+    }
+
+    proptest! {
+        #[test]
+        fn rotate_matches_click_by_click(dial in arb_dial(), delta in -500..500_i64) {
+            let (pos, nb_zero_passes) = turn_click_by_click(dial.size(), dial.position(), delta);
+            let mut rotated = dial;
+            prop_assert_eq!(
+                rotated.rotate(delta),
+                RotationOutcome {
+                    nb_zero_passes,
+                    landed_on_zero: pos == 0 && delta != 0,
+                    position: pos,
+                }
+            );
+        }
+    }
 }
@@ -0,0 +1,457 @@
+use std::io::BufRead;
+use std::rc::Rc;
+
+use crate::dial::Dial;
+use crate::{Rotation, parse_rotation};
+
+/// Deepest nesting of repeats accepted, so that parsing cannot overflow the
+/// stack.
+const MAX_NESTING: usize = 64;
+
+/// Longest word accepted, far more than any valid instruction needs.
+const MAX_WORD_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Star,
+    Open,
+    Close,
+    EndOfLine,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Instruction {
+    Rotate(Rotation),
+    Set(i64),
+    Reset,
+    Repeat(u64, Rc<[Instruction]>),
+}
+
+/// Instructions being executed, from innermost repeat to top-level
+/// instruction.
+#[derive(Debug, Clone)]
+struct Frame {
+    instructions: Rc<[Instruction]>,
+    /// Index of next instruction to execute.
+    next: usize,
+    /// Number of times instructions are to be executed again once done.
+    nb_repeats_left: u64,
+    /// Number of rotations compiled before current execution of instructions
+    /// started.
+    nb_rotations_before: u64,
+}
+
+/// Translates source lines into rotations, keeping track of dial position to
+/// resolve absolute instructions.
+///
+/// Each line holds instructions separated by commas or white space, optionally
+/// followed by a `#` comment.  Instructions are:
+///
+/// - `L<n>` and `R<n>`: turns dial `n` clicks left or right.
+/// - `SET <pos>`: turns dial right until it points at `pos`.
+/// - `RESET`: turns dial right until it points at its start position.
+/// - `<n>*(<instructions>)`: repeats instructions `n` times.
+///
+/// Repeats are expanded as rotations are requested, so that they may be
+/// repeated any number of times.
+#[derive(Debug, Clone)]
+pub struct Compiler {
+    start: Dial,
+    dial: Dial,
+    frames: Vec<Frame>,
+    nb_rotations: u64,
+}
+
+impl Compiler {
+    /// Creates compiler for instructions to be executed on `start`.
+    pub fn new(start: Dial) -> Self {
+        Self {
+            start,
+            dial: start,
+            frames: Vec::new(),
+            nb_rotations: 0,
+        }
+    }
+
+    /// Compiles next instruction of `source`, whose rotations are returned
+    /// by `next_rotation` after those of previous instructions.  Returns false
+    /// at end of source.
+    pub fn compile_next<R: BufRead>(&mut self, source: &mut Source<R>) -> anyhow::Result<bool> {
+        let instruction = loop {
+            match source.peek()? {
+                None => return Ok(false),
+                Some(Token::EndOfLine) => {
+                    source.next()?;
+                }
+                Some(Token::Close) => anyhow::bail!("unexpected {:?}", Token::Close),
+                Some(_) => break parse_instruction(source, 0)?,
+            }
+        };
+        let frame = Frame {
+            instructions: Rc::new([instruction]),
+            next: 0,
+            nb_repeats_left: 0,
+            nb_rotations_before: self.nb_rotations,
+        };
+        self.frames.insert(0, frame);
+        Ok(true)
+    }
+
+    /// Returns next rotation of compiled instructions, or `None` once they
+    /// are all done.
+    pub fn next_rotation(&mut self) -> Option<anyhow::Result<Rotation>> {
+        loop {
+            let frame = self.frames.last_mut()?;
+            let Some(instruction) = frame.instructions.get(frame.next).cloned() else {
+                // Dial did not move if instructions yielded no rotation, so
+                // that repeating them would not either.
+                if frame.nb_repeats_left > 0 && frame.nb_rotations_before < self.nb_rotations {
+                    frame.nb_repeats_left -= 1;
+                    frame.next = 0;
+                    frame.nb_rotations_before = self.nb_rotations;
+                } else {
+                    self.frames.pop();
+                }
+                continue;
+            };
+            frame.next += 1;
+            let steps = match instruction {
+                Instruction::Rotate(rotation) => rotation.steps,
+                Instruction::Set(pos) => match self.steps_to(pos) {
+                    Ok(steps) => steps,
+                    Err(e) => return Some(Err(e)),
+                },
+                Instruction::Reset => match self.steps_to(self.start.position()) {
+                    Ok(steps) => steps,
+                    Err(e) => return Some(Err(e)),
+                },
+                Instruction::Repeat(n, body) => {
+                    if n > 0 {
+                        self.frames.push(Frame {
+                            instructions: body,
+                            next: 0,
+                            nb_repeats_left: n - 1,
+                            nb_rotations_before: self.nb_rotations,
+                        });
+                    }
+                    continue;
+                }
+            };
+            // SET to current position needs no rotation.
+            if matches!(instruction, Instruction::Rotate(_)) || steps != 0 {
+                self.dial.rotate(steps);
+                self.nb_rotations += 1;
+                return Some(Ok(Rotation { steps }));
+            }
+        }
+    }
+
+    /// Returns number of clicks to turn dial right so that it points at
+    /// `pos`.
+    fn steps_to(&self, pos: i64) -> anyhow::Result<i64> {
+        let size = self.dial.size();
+        if !(0..size).contains(&pos) {
+            anyhow::bail!("SET position {} out of range: must be in 0..{}", pos, size);
+        }
+        Ok((pos - self.dial.position()).rem_euclid(size))
+    }
+}
+
+/// Source code read one token at a time, so that lines of any length take
+/// bounded memory.
+#[derive(Debug)]
+pub struct Source<R> {
+    input: R,
+    line_number: usize,
+    /// True if last token ended a line, which is only counted once next token
+    /// is read so that errors are reported on the line they occur.
+    at_end_of_line: bool,
+    peeked: Option<Token>,
+}
+
+impl<R: BufRead> Source<R> {
+    pub fn new(input: R) -> Self {
+        Self {
+            input,
+            line_number: 1,
+            at_end_of_line: false,
+            peeked: None,
+        }
+    }
+
+    /// Returns number of line holding last token read, starting at 1.
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    fn peek(&mut self) -> anyhow::Result<Option<&Token>> {
+        if self.peeked.is_none() {
+            self.peeked = self.read_token()?;
+        }
+        Ok(self.peeked.as_ref())
+    }
+
+    fn next(&mut self) -> anyhow::Result<Option<Token>> {
+        match self.peeked.take() {
+            Some(token) => Ok(Some(token)),
+            None => self.read_token(),
+        }
+    }
+
+    fn read_token(&mut self) -> anyhow::Result<Option<Token>> {
+        if std::mem::take(&mut self.at_end_of_line) {
+            self.line_number += 1;
+        }
+        loop {
+            let Some(b) = self.peek_byte()? else {
+                return Ok(None);
+            };
+            let token = match b {
+                b'\n' => {
+                    self.at_end_of_line = true;
+                    Token::EndOfLine
+                }
+                b'*' => Token::Star,
+                b'(' => Token::Open,
+                b')' => Token::Close,
+                b'#' => {
+                    self.skip_comment()?;
+                    continue;
+                }
+                b',' => {
+                    self.input.consume(1);
+                    continue;
+                }
+                _ if b.is_ascii_whitespace() => {
+                    self.input.consume(1);
+                    continue;
+                }
+                _ => return self.read_word().map(Some),
+            };
+            self.input.consume(1);
+            return Ok(Some(token));
+        }
+    }
+
+    fn read_word(&mut self) -> anyhow::Result<Token> {
+        let mut word = Vec::new();
+        while let Some(b) = self.peek_byte()?
+            && !(b.is_ascii_whitespace() || matches!(b, b',' | b'*' | b'(' | b')' | b'#'))
+        {
+            if word.len() == MAX_WORD_LEN {
+                anyhow::bail!(
+                    "instruction too long: {}...",
+                    String::from_utf8_lossy(&word)
+                );
+            }
+            word.push(b);
+            self.input.consume(1);
+        }
+        String::from_utf8(word)
+            .map(Token::Word)
+            .map_err(|e| anyhow::anyhow!("invalid UTF-8: {:?}", e.as_bytes()))
+    }
+
+    /// Skips everything up to end of line.
+    fn skip_comment(&mut self) -> anyhow::Result<()> {
+        loop {
+            let buffer = self.input.fill_buf()?;
+            if buffer.is_empty() {
+                return Ok(());
+            }
+            match buffer.iter().position(|&b| b == b'\n') {
+                Some(i) => {
+                    self.input.consume(i);
+                    return Ok(());
+                }
+                None => {
+                    let n = buffer.len();
+                    self.input.consume(n);
+                }
+            }
+        }
+    }
+
+    fn peek_byte(&mut self) -> anyhow::Result<Option<u8>> {
+        Ok(self.input.fill_buf()?.first().copied())
+    }
+}
+
+/// Parses instructions of a repeat until closing parenthesis, `depth` being
+/// number of enclosing repeats.
+fn parse_sequence<R: BufRead>(
+    source: &mut Source<R>,
+    depth: usize,
+) -> anyhow::Result<Vec<Instruction>> {
+    let mut instructions = Vec::new();
+    while let Some(token) = source.peek()? {
+        if matches!(token, Token::Close | Token::EndOfLine) {
+            break;
+        }
+        instructions.push(parse_instruction(source, depth)?);
+    }
+    Ok(instructions)
+}
+
+fn parse_instruction<R: BufRead>(
+    source: &mut Source<R>,
+    depth: usize,
+) -> anyhow::Result<Instruction> {
+    let word = match source.next()? {
+        Some(Token::Word(word)) => word,
+        token => anyhow::bail!("expected instruction, found {:?}", token),
+    };
+    match word.as_str() {
+        "SET" => {
+            let Some(Token::Word(pos)) = source.next()? else {
+                anyhow::bail!("missing position after SET");
+            };
+            let pos = pos
+                .parse()
+                .map_err(|e| anyhow::anyhow!("invalid SET position: {}: {}", pos, e))?;
+            Ok(Instruction::Set(pos))
+        }
+        "RESET" => Ok(Instruction::Reset),
+        _ if word.starts_with(|c: char| c.is_ascii_digit()) => {
+            let n = word
+                .parse()
+                .map_err(|e| anyhow::anyhow!("invalid repeat count: {}: {}", word, e))?;
+            let Some(Token::Star) = source.next()? else {
+                anyhow::bail!("missing '*' after repeat count {}", n);
+            };
+            let Some(Token::Open) = source.next()? else {
+                anyhow::bail!("missing '(' after {}*", n);
+            };
+            if depth == MAX_NESTING {
+                anyhow::bail!("repeats nested more than {} deep", MAX_NESTING);
+            }
+            let body = parse_sequence(source, depth + 1)?;
+            let Some(Token::Close) = source.next()? else {
+                anyhow::bail!("missing ')' after {}*(", n);
+            };
+            Ok(Instruction::Repeat(n, body.into()))
+        }
+        _ => Ok(Instruction::Rotate(parse_rotation(&word)?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn compile(text: &str) -> anyhow::Result<Vec<i64>> {
+        let mut compiler = Compiler::new(Dial::new(100, 50).unwrap());
+        let mut source = Source::new(Cursor::new(text));
+        while compiler.compile_next(&mut source)? {}
+        rotations(&mut compiler)
+    }
+
+    fn rotations(compiler: &mut Compiler) -> anyhow::Result<Vec<i64>> {
+        std::iter::from_fn(|| compiler.next_rotation())
+            .map(|rotation| rotation.map(|r| r.steps))
+            .collect()
+    }
+
+    #[test]
+    fn single_rotation() {
+        assert_eq!(compile("R10").unwrap(), vec![10]);
+        assert_eq!(compile("  L5 ").unwrap(), vec![-5]);
+    }
+
+    #[test]
+    fn separators() {
+        assert_eq!(compile("R10,L5 R1 ,L2").unwrap(), vec![10, -5, 1, -2]);
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(compile("# nothing here").unwrap(), vec![]);
+        assert_eq!(compile("R10 # L5").unwrap(), vec![10]);
+    }
+
+    #[test]
+    fn repeat() {
+        assert_eq!(compile("3*(R10 L5)").unwrap(), vec![10, -5, 10, -5, 10, -5]);
+        assert_eq!(compile("2 * ( R1 )").unwrap(), vec![1, 1]);
+        assert_eq!(compile("2*(L1 2*(R2))").unwrap(), vec![-1, 2, 2, -1, 2, 2]);
+        assert_eq!(compile("0*(R1)").unwrap(), vec![]);
+        assert_eq!(compile("2*() R1").unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn huge_repeat() {
+        // Rotations are produced as needed.
+        let mut compiler = Compiler::new(Dial::new(100, 50).unwrap());
+        let mut source = Source::new(Cursor::new("100000000000*(R1 L2)"));
+        assert!(compiler.compile_next(&mut source).unwrap());
+        let first: Vec<_> = std::iter::from_fn(|| compiler.next_rotation())
+            .take(3)
+            .map(|r| r.unwrap().steps)
+            .collect();
+        assert_eq!(first, vec![1, -2, 1]);
+        // Repeats leaving dial in place are cut short.
+        assert_eq!(
+            compile("R50 1000000000000*(SET 0 1000000000000*(SET 0))").unwrap(),
+            vec![50]
+        );
+    }
+
+    #[test]
+    fn nesting() {
+        let nested = |depth| format!("{}R1{}", "1*(".repeat(depth), ")".repeat(depth));
+        assert_eq!(compile(&nested(MAX_NESTING)).unwrap(), vec![1]);
+        let e = compile(&nested(MAX_NESTING + 1)).unwrap_err();
+        assert_eq!(e.to_string(), "repeats nested more than 64 deep");
+        assert!(compile(&nested(200_000)).is_err());
+    }
+
+    #[test]
+    fn set() {
+        assert_eq!(compile("SET 60").unwrap(), vec![10]);
+        assert_eq!(compile("SET 40").unwrap(), vec![90]);
+        assert_eq!(compile("SET 50").unwrap(), vec![]);
+        assert_eq!(compile("R20 SET 0").unwrap(), vec![20, 30]);
+    }
+
+    #[test]
+    fn reset() {
+        assert_eq!(compile("RESET").unwrap(), vec![]);
+        assert_eq!(compile("R20, RESET").unwrap(), vec![20, 80]);
+    }
+
+    #[test]
+    fn state_spans_lines() {
+        assert_eq!(compile("R10\nRESET\n").unwrap(), vec![10, 90]);
+        assert_eq!(compile("R10 # L5\r\nL5").unwrap(), vec![10, -5]);
+    }
+
+    #[test]
+    fn line_numbers() {
+        let mut source = Source::new(Cursor::new("R1\n\n# comment\n2*(R1\nR2"));
+        let mut compiler = Compiler::new(Dial::new(100, 50).unwrap());
+        assert!(compiler.compile_next(&mut source).unwrap());
+        assert_eq!(source.line_number(), 1);
+        assert!(compiler.compile_next(&mut source).is_err());
+        assert_eq!(source.line_number(), 4);
+    }
+
+    #[test]
+    fn long_words() {
+        assert!(compile(&format!("R{}", "1".repeat(1000))).is_err());
+        assert!(compile("R\u{e9}").is_err());
+        assert_eq!(compile(&"R1,".repeat(10_000)).unwrap().len(), 10_000);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(compile("X10").is_err());
+        assert!(compile("SET").is_err());
+        assert!(compile("SET 100").is_err());
+        assert!(compile("3(R1)").is_err());
+        assert!(compile("3*R1").is_err());
+        assert!(compile("3*(R1").is_err());
+        assert!(compile("R1)").is_err());
+    }
+}
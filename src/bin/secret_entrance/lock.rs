@@ -0,0 +1,289 @@
+use std::io::BufRead;
+use std::str::FromStr;
+
+use crate::dial::Dial;
+use crate::{Rotation, add_zeroes, parse_rotation};
+
+/// Coupling between two dials of a lock: turning `driver` by `n` clicks also
+/// turns `driven` by `n * ratio` clicks.  A negative ratio reverses direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gear {
+    pub driver: usize,
+    pub driven: usize,
+    pub ratio: i64,
+}
+
+impl FromStr for Gear {
+    type Err = anyhow::Error;
+
+    /// Parses a gear written as `driver:driven:ratio`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split(':').collect();
+        let [driver, driven, ratio] = fields[..] else {
+            anyhow::bail!("invalid gear: {} (expected driver:driven:ratio)", s);
+        };
+        Ok(Gear {
+            driver: driver
+                .parse()
+                .map_err(|e| anyhow::anyhow!("invalid gear driver: {}: {}", driver, e))?,
+            driven: driven
+                .parse()
+                .map_err(|e| anyhow::anyhow!("invalid gear driven dial: {}: {}", driven, e))?,
+            ratio: ratio
+                .parse()
+                .map_err(|e| anyhow::anyhow!("invalid gear ratio: {}: {}", ratio, e))?,
+        })
+    }
+}
+
+/// A combination lock made of several dials, possibly geared together.
+#[derive(Debug, Clone)]
+pub struct Lock {
+    dials: Vec<Dial>,
+    gears: Vec<Gear>,
+}
+
+impl Lock {
+    pub fn new(dials: Vec<Dial>, gears: Vec<Gear>) -> anyhow::Result<Self> {
+        if dials.is_empty() {
+            anyhow::bail!("lock must have at least one dial");
+        }
+        for gear in &gears {
+            if gear.driver >= dials.len() || gear.driven >= dials.len() {
+                anyhow::bail!(
+                    "gear {}:{} refers to missing dial: lock has {} dials",
+                    gear.driver,
+                    gear.driven,
+                    dials.len()
+                );
+            }
+        }
+        let lock = Self { dials, gears };
+        for i in 0..lock.dials.len() {
+            if lock.drives(i, i) {
+                anyhow::bail!("gears form a cycle through dial {}", i);
+            }
+        }
+        Ok(lock)
+    }
+
+    pub fn dials(&self) -> &[Dial] {
+        &self.dials
+    }
+
+    /// Turns dial `i` by `steps` clicks along with all dials it drives and
+    /// returns number of times each dial pointed at zero.
+    pub fn rotate(&mut self, i: usize, steps: i64) -> anyhow::Result<Vec<u64>> {
+        if i >= self.dials.len() {
+            anyhow::bail!("no dial {}: lock has {} dials", i, self.dials.len());
+        }
+        let mut nb_zeroes = vec![0; self.dials.len()];
+        // Gears form a DAG so this terminates.
+        let mut pending = vec![(i, steps)];
+        while let Some((i, steps)) = pending.pop() {
+            nb_zeroes[i] = add_zeroes(nb_zeroes[i], self.dials[i].rotate(steps).nb_zero_passes)?;
+            for gear in self.gears.iter().filter(|g| g.driver == i) {
+                let driven_steps = steps.checked_mul(gear.ratio).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "rotation of dial {} overflows: {} * {}",
+                        gear.driven,
+                        steps,
+                        gear.ratio
+                    )
+                })?;
+                pending.push((gear.driven, driven_steps));
+            }
+        }
+        Ok(nb_zeroes)
+    }
+
+    /// Returns true if turning dial `from` turns dial `to` through one or more
+    /// gears.
+    fn drives(&self, from: usize, to: usize) -> bool {
+        let mut visited = vec![false; self.dials.len()];
+        let mut pending = vec![from];
+        while let Some(i) = pending.pop() {
+            for gear in self.gears.iter().filter(|g| g.driver == i) {
+                if gear.driven == to {
+                    return true;
+                }
+                if !visited[gear.driven] {
+                    visited[gear.driven] = true;
+                    pending.push(gear.driven);
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Parses comma-separated list of dials written as `size` or `size@start`.
+/// Dials without explicit start point at `default_start`.
+pub fn parse_dials(s: &str, default_start: i64) -> anyhow::Result<Vec<Dial>> {
+    s.split(',')
+        .map(|spec| {
+            let (size, start) = match spec.split_once('@') {
+                Some((size, start)) => (
+                    size,
+                    start
+                        .parse()
+                        .map_err(|e| anyhow::anyhow!("invalid dial start: {}: {}", start, e))?,
+                ),
+                None => (spec, default_start),
+            };
+            let size = size
+                .parse()
+                .map_err(|e| anyhow::anyhow!("invalid dial size: {}: {}", size, e))?;
+            Dial::new(size, start)
+        })
+        .collect()
+}
+
+/// Parses comma-separated list of gears.
+pub fn parse_gears(s: &str) -> anyhow::Result<Vec<Gear>> {
+    s.split(',').map(str::parse).collect()
+}
+
+/// Rotation of a single dial of a lock.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LockRotation {
+    pub dial: usize,
+    pub rotation: Rotation,
+}
+
+/// Reads instructions of the form `<dial>:L<n>` or `<dial>:R<n>`, one per line.
+pub fn read_lock_instructions<R: BufRead>(input: &mut R) -> anyhow::Result<Vec<LockRotation>> {
+    let mut instructions = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (dial, rotation) = line
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("line {}: missing dial index: {}", line_number, line))?;
+        let dial = dial.parse().map_err(|e| {
+            anyhow::anyhow!("line {}: invalid dial index: {}: {}", line_number, dial, e)
+        })?;
+        let rotation =
+            parse_rotation(rotation).map_err(|e| anyhow::anyhow!("line {}: {}", line_number, e))?;
+        instructions.push(LockRotation { dial, rotation });
+    }
+    Ok(instructions)
+}
+
+/// Turns dials of `lock` as specified in `instructions` and returns number of
+/// times each dial pointed to zero while being turned.
+pub fn execute_lock_instructions(
+    mut lock: Lock,
+    instructions: &[LockRotation],
+) -> anyhow::Result<Vec<u64>> {
+    let mut nb_zeroes: Vec<u64> = lock
+        .dials()
+        .iter()
+        .map(|d| if d.position() == 0 { 1 } else { 0 })
+        .collect();
+    for instruction in instructions {
+        let counts = lock.rotate(instruction.dial, instruction.rotation.steps)?;
+        for (total, n) in nb_zeroes.iter_mut().zip(counts) {
+            *total = add_zeroes(*total, n)?;
+        }
+    }
+    Ok(nb_zeroes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn lock(dials: &str, gears: &str) -> Lock {
+        let gears = if gears.is_empty() {
+            Vec::new()
+        } else {
+            parse_gears(gears).unwrap()
+        };
+        Lock::new(parse_dials(dials, 0).unwrap(), gears).unwrap()
+    }
+
+    #[test]
+    fn parse_dial_specs() {
+        let dials = parse_dials("100,10@5", 50).unwrap();
+        assert_eq!(
+            dials,
+            vec![Dial::new(100, 50).unwrap(), Dial::new(10, 5).unwrap()]
+        );
+        assert!(parse_dials("10@10", 0).is_err());
+        assert!(parse_dials("x", 0).is_err());
+    }
+
+    #[test]
+    fn parse_gear_specs() {
+        assert_eq!(
+            parse_gears("0:1:-2").unwrap(),
+            vec![Gear {
+                driver: 0,
+                driven: 1,
+                ratio: -2
+            }]
+        );
+        assert!(parse_gears("0:1").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_gears() {
+        let dials = parse_dials("10,10", 0).unwrap();
+        assert!(Lock::new(dials.clone(), parse_gears("0:2:1").unwrap()).is_err());
+        assert!(Lock::new(dials.clone(), parse_gears("0:1:1,1:0:1").unwrap()).is_err());
+        assert!(Lock::new(dials, parse_gears("0:0:1").unwrap()).is_err());
+    }
+
+    #[test]
+    fn independent_dials() {
+        let mut lock = lock("10,20@5", "");
+        assert_eq!(lock.rotate(1, 20).unwrap(), vec![0, 1]);
+        assert_eq!(lock.dials()[0].position(), 0);
+        assert_eq!(lock.dials()[1].position(), 5);
+        assert!(lock.rotate(2, 1).is_err());
+    }
+
+    #[test]
+    fn geared_dials() {
+        // Dial 0 drives dial 1 twice as fast in reverse, which drives dial 2.
+        let mut lock = lock("10@5,10@5,100", "0:1:-2,1:2:1");
+        assert_eq!(lock.rotate(0, 5).unwrap(), vec![1, 1, 0]);
+        let positions: Vec<i64> = lock.dials().iter().map(Dial::position).collect();
+        assert_eq!(positions, vec![0, 5, 90]);
+    }
+
+    #[test]
+    fn read_instructions() {
+        let mut input = Cursor::new(b"0:R10\n\n2:L5\n");
+        let instructions = read_lock_instructions(&mut input).unwrap();
+        assert_eq!(
+            instructions,
+            vec![
+                LockRotation {
+                    dial: 0,
+                    rotation: Rotation { steps: 10 }
+                },
+                LockRotation {
+                    dial: 2,
+                    rotation: Rotation { steps: -5 }
+                },
+            ]
+        );
+        assert!(read_lock_instructions(&mut Cursor::new(b"R10\n")).is_err());
+        assert!(read_lock_instructions(&mut Cursor::new(b"x:R10\n")).is_err());
+    }
+
+    #[test]
+    fn execute_instructions() {
+        let mut input = Cursor::new(b"0:R10\n1:L25\n");
+        let instructions = read_lock_instructions(&mut input).unwrap();
+        let nb_zeroes = execute_lock_instructions(lock("10,10@5", ""), &instructions).unwrap();
+        assert_eq!(nb_zeroes, vec![2, 3]);
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::VecDeque;
+
+use crate::Rotation;
+use crate::dial::Dial;
+
+/// Largest number of (position, zero passes) states searched, so that search
+/// needs at most a few hundred megabytes.
+const MAX_STATES: usize = 1 << 24;
+
+/// Restrictions on rotation sequences returned by `find_rotations()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraints {
+    /// Maximum number of rotations in sequence.
+    pub max_instructions: usize,
+    /// Number of clicks a single rotation may turn the dial by, in either
+    /// direction.
+    pub step_sizes: Vec<i64>,
+    /// If set, exact number of times the dial must point at zero while being
+    /// turned, not counting the start position.
+    pub nb_zero_passes: Option<u64>,
+}
+
+/// Returns a shortest sequence of rotations that turns `start` to `target`
+/// while satisfying `constraints`, or `None` if there is no such sequence.
+pub fn find_rotations(
+    start: Dial,
+    target: i64,
+    constraints: &Constraints,
+) -> anyhow::Result<Option<Vec<Rotation>>> {
+    let size = start.size();
+    if !(0..size).contains(&target) {
+        anyhow::bail!("target {} out of range: must be in 0..{}", target, size);
+    }
+    if constraints.step_sizes.iter().any(|&s| s <= 0) {
+        anyhow::bail!("step sizes must be positive");
+    }
+
+    // Each rotation changes position by a multiple of gcd(steps, size) so
+    // other targets are unreachable.
+    let divisor = constraints
+        .step_sizes
+        .iter()
+        .fold(size, |acc, &s| gcd(acc, s));
+    if (target - start.position()).rem_euclid(divisor) != 0 {
+        return Ok(None);
+    }
+
+    // Breadth-first search over (position, zero passes so far) states.  Zero
+    // passes are capped one above the requirement as any greater count is
+    // equally hopeless.
+    let nb_counts = match constraints.nb_zero_passes {
+        Some(n) => usize::try_from(n)
+            .ok()
+            .and_then(|n| n.checked_add(2))
+            .ok_or_else(|| anyhow::anyhow!("too many zero passes required: {}", n))?,
+        None => 1,
+    };
+    let nb_states = (size as usize)
+        .checked_mul(nb_counts)
+        .filter(|&n| n <= MAX_STATES)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "search space too large: {} positions times {} zero pass counts exceeds {} states",
+                size,
+                nb_counts,
+                MAX_STATES
+            )
+        })?;
+    let cap = nb_counts as u64 - 1;
+    let state_of = |pos: i64, zeroes: u64| pos as usize * nb_counts + zeroes.min(cap) as usize;
+    let is_goal = |pos: i64, zeroes: u64| {
+        pos == target && constraints.nb_zero_passes.is_none_or(|n| n == zeroes)
+    };
+
+    // For each visited state, the state it was reached from and the rotation
+    // used.
+    let mut parents: Vec<Option<(usize, i64)>> = vec![None; nb_states];
+    let start_state = state_of(start.position(), 0);
+    let mut pending = VecDeque::from([(start, 0_u64, 0_usize)]);
+    let mut visited = vec![false; parents.len()];
+    visited[start_state] = true;
+
+    while let Some((dial, zeroes, depth)) = pending.pop_front() {
+        let state = state_of(dial.position(), zeroes);
+        if is_goal(dial.position(), zeroes) {
+            return Ok(Some(unwind(&parents, start_state, state)));
+        }
+        if depth == constraints.max_instructions {
+            continue;
+        }
+        for &step in &constraints.step_sizes {
+            for steps in [step, -step] {
+                let mut next = dial;
+                let outcome = next.rotate(steps);
+                let next_zeroes = zeroes.saturating_add(outcome.nb_zero_passes).min(cap);
+                let next_state = state_of(next.position(), next_zeroes);
+                if !visited[next_state] {
+                    visited[next_state] = true;
+                    parents[next_state] = Some((state, steps));
+                    pending.push_back((next, next_zeroes, depth + 1));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Returns rotations leading from `start_state` to `state`.
+fn unwind(parents: &[Option<(usize, i64)>], start_state: usize, mut state: usize) -> Vec<Rotation> {
+    let mut rotations = Vec::new();
+    while state != start_state {
+        let (parent, steps) = parents[state].unwrap();
+        rotations.push(Rotation { steps });
+        state = parent;
+    }
+    rotations.reverse();
+    rotations
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constraints(step_sizes: &[i64], nb_zero_passes: Option<u64>) -> Constraints {
+        Constraints {
+            max_instructions: 10,
+            step_sizes: step_sizes.to_vec(),
+            nb_zero_passes,
+        }
+    }
+
+    /// Checks that `rotations` turn `start` to `target` with given number of
+    /// zero passes.
+    fn check(mut start: Dial, target: i64, nb_zero_passes: u64, rotations: &[Rotation]) {
+        let mut zeroes = 0;
+        for r in rotations {
+            zeroes += start.rotate(r.steps).nb_zero_passes;
+        }
+        assert_eq!(start.position(), target);
+        assert_eq!(zeroes, nb_zero_passes);
+    }
+
+    #[test]
+    fn already_on_target() {
+        let start = Dial::new(100, 50).unwrap();
+        let r = find_rotations(start, 50, &constraints(&[1], None)).unwrap();
+        assert_eq!(r, Some(vec![]));
+    }
+
+    #[test]
+    fn shortest_sequence() {
+        let start = Dial::new(100, 50).unwrap();
+        let r = find_rotations(start, 0, &constraints(&[10, 25], None))
+            .unwrap()
+            .unwrap();
+        assert_eq!(r, vec![Rotation { steps: 25 }, Rotation { steps: 25 }]);
+    }
+
+    #[test]
+    fn required_zero_passes() {
+        let start = Dial::new(100, 50).unwrap();
+        let r = find_rotations(start, 50, &constraints(&[50], Some(2)))
+            .unwrap()
+            .unwrap();
+        assert_eq!(r.len(), 4);
+        check(start, 50, 2, &r);
+    }
+
+    #[test]
+    fn unreachable_target() {
+        let start = Dial::new(100, 50).unwrap();
+        assert_eq!(
+            find_rotations(start, 1, &constraints(&[10], None)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn too_many_instructions_needed() {
+        let start = Dial::new(100, 0).unwrap();
+        let c = Constraints {
+            max_instructions: 2,
+            ..constraints(&[1], None)
+        };
+        assert_eq!(find_rotations(start, 50, &c).unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        let start = Dial::new(100, 0).unwrap();
+        assert!(find_rotations(start, 100, &constraints(&[1], None)).is_err());
+        assert!(find_rotations(start, 1, &constraints(&[0], None)).is_err());
+        assert!(find_rotations(start, 1, &constraints(&[1], Some(u64::MAX))).is_err());
+        let start = Dial::new(i64::MAX, 0).unwrap();
+        assert!(find_rotations(start, 1, &constraints(&[1], Some(1))).is_err());
+        assert!(find_rotations(start, 1, &constraints(&[1], None)).is_err());
+        let start = Dial::new(1000, 0).unwrap();
+        assert!(find_rotations(start, 1, &constraints(&[1], Some(100_000))).is_err());
+    }
+}
@@ -1,73 +1,364 @@
-use std::io::BufRead;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Write};
 
 mod dial;
+mod language;
+mod lock;
+mod search;
+mod stats;
+mod trace;
 
+use advent_code_25::CommandLine;
+use advent_code_25::cycle;
 use dial::Dial;
+use language::{Compiler, Source};
+use lock::Lock;
+use stats::Statistics;
+use trace::TraceFormat;
+
+/// Position the dial points at before executing instructions.
+const DEFAULT_START: i64 = 50;
 
 fn main() -> anyhow::Result<()> {
-    let input = advent_code_25::open_input_file()?;
-    let answer = solve_for(input)?;
+    let cmd_line = CommandLine::from_env();
+    if let Some(dials) = cmd_line.option::<String>("dials")? {
+        return run_lock(&cmd_line, &dials);
+    }
+    let size = cmd_line.option("size")?.unwrap_or(dial::DEFAULT_SIZE);
+    let start = cmd_line.option("start")?.unwrap_or(DEFAULT_START);
+    let dial = Dial::new(size, start)?;
+    if cmd_line.positional(0) == Some("search") {
+        return run_search(&cmd_line, dial);
+    }
+    let mut input = cmd_line.open_input_file()?;
+    if let Some(format) = cmd_line.option::<TraceFormat>("trace")? {
+        let instructions = read_instructions(&mut input, dial)?;
+        let trace = trace::trace_instructions(dial, &instructions);
+        match cmd_line.option::<String>("trace-output")? {
+            Some(path) => {
+                let file = File::create(&path)
+                    .map_err(|e| anyhow::anyhow!("failed to create {}: {}", path, e))?;
+                let mut output = BufWriter::new(file);
+                trace::write_trace(&mut output, &trace, format)?;
+                output.flush()?;
+            }
+            None => trace::write_trace(std::io::stdout().lock(), &trace, format)?,
+        }
+        println!("answer: {}", execute_instructions(dial, &instructions)?);
+        return Ok(());
+    }
+    if let Some(times) = cmd_line.option("times")? {
+        let instructions = read_instructions(&mut input, dial)?;
+        println!(
+            "answer: {}",
+            execute_repeatedly(dial, &instructions, times)?
+        );
+        return Ok(());
+    }
+    if cmd_line.flag("stats") {
+        let mut stats = Statistics::new(dial)?;
+        let rotations = rotations_from_input(input, dial).map(|rotation| {
+            let rotation = rotation?;
+            stats.record(rotation)?;
+            Ok(rotation)
+        });
+        let mut progress = Progress::new(dial);
+        for p in execute_stream(dial, rotations) {
+            progress = p?;
+        }
+        stats.write_report(std::io::stdout().lock())?;
+        println!("answer: {}", progress.nb_zeroes);
+        return Ok(());
+    }
+    let report_every = cmd_line.option::<u64>("progress")?;
+    let answer = solve_for(input, dial, |progress| {
+        if report_every.is_some_and(|n| n > 0 && progress.nb_rotations.is_multiple_of(n)) {
+            eprintln!(
+                "after {} rotations: {} zeroes",
+                progress.nb_rotations, progress.nb_zeroes
+            );
+        }
+    })?;
     println!("answer: {}", answer);
     Ok(())
 }
 
-fn solve_for<R: BufRead>(mut input: R) -> anyhow::Result<usize> {
-    let instructions = read_instructions(&mut input)?;
-    Ok(execute_instructions(Dial::new(50), &instructions))
+/// Simulates a lock made of dials described by `dials` and prints number of
+/// zeroes for each dial.
+fn run_lock(cmd_line: &CommandLine, dials: &str) -> anyhow::Result<()> {
+    let dials = lock::parse_dials(dials, DEFAULT_START)?;
+    let gears = match cmd_line.option::<String>("gears")? {
+        Some(gears) => lock::parse_gears(&gears)?,
+        None => Vec::new(),
+    };
+    let lock = Lock::new(dials, gears)?;
+    let mut input = cmd_line.open_input_file()?;
+    let instructions = lock::read_lock_instructions(&mut input)?;
+    let nb_zeroes = lock::execute_lock_instructions(lock, &instructions)?;
+    for (i, n) in nb_zeroes.iter().enumerate() {
+        println!("dial {}: {}", i, n);
+    }
+    Ok(())
+}
+
+/// Prints a shortest sequence of rotations turning `dial` to the target
+/// position given on command line.
+fn run_search(cmd_line: &CommandLine, dial: Dial) -> anyhow::Result<()> {
+    let target = cmd_line
+        .option("target")?
+        .ok_or_else(|| anyhow::anyhow!("missing --target"))?;
+    let step_sizes = match cmd_line.option::<String>("steps")? {
+        Some(steps) => steps
+            .split(',')
+            .map(|s| {
+                s.parse()
+                    .map_err(|e| anyhow::anyhow!("invalid step size: {}: {}", s, e))
+            })
+            .collect::<anyhow::Result<Vec<i64>>>()?,
+        None => vec![1],
+    };
+    let constraints = search::Constraints {
+        max_instructions: cmd_line.option("max-instructions")?.unwrap_or(usize::MAX),
+        step_sizes,
+        nb_zero_passes: cmd_line.option("zero-passes")?,
+    };
+    match search::find_rotations(dial, target, &constraints)? {
+        Some(rotations) => {
+            for rotation in rotations {
+                println!("{}", rotation);
+            }
+            Ok(())
+        }
+        None => anyhow::bail!("no rotation sequence satisfies constraints"),
+    }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-enum Direction {
-    Left,
-    Right,
+/// Executes instructions one at a time as they are read from `input`, calling
+/// `report` after each rotation.
+fn solve_for<R: BufRead>(
+    input: R,
+    dial: Dial,
+    mut report: impl FnMut(&Progress),
+) -> anyhow::Result<u64> {
+    let mut progress = Progress::new(dial);
+    for p in execute_stream(dial, rotations_from_input(input, dial)) {
+        progress = p?;
+        report(&progress);
+    }
+    Ok(progress.nb_zeroes)
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Dial rotation of `steps` clicks, to the right if positive or to the left if
+/// negative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Rotation {
-    direction: Direction,
-    steps: usize,
-}
-
-fn read_instructions<R: BufRead>(input: &mut R) -> anyhow::Result<Vec<Rotation>> {
-    let mut instructions = Vec::new();
-    for (i, line) in input.lines().enumerate() {
-        let line_number = i + 1;
-        let line = line?;
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
+    steps: i64,
+}
+
+impl fmt::Display for Rotation {
+    /// Formats rotation as parsed by `read_instructions()`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let direction = if self.steps < 0 { 'L' } else { 'R' };
+        write!(f, "{}{}", direction, self.steps.unsigned_abs())
+    }
+}
+
+/// Reads instructions to be executed on `dial` and compiles them into
+/// rotations.
+fn read_instructions<R: BufRead>(input: &mut R, dial: Dial) -> anyhow::Result<Vec<Rotation>> {
+    rotations_from_input(input, dial).collect()
+}
+
+/// Returns iterator that reads and compiles instructions to be executed on
+/// `dial` one token at a time.
+fn rotations_from_input<R: BufRead>(
+    input: R,
+    dial: Dial,
+) -> impl Iterator<Item = anyhow::Result<Rotation>> {
+    RotationIter::new(input, dial)
+}
+
+/// Iterator that yields Rotation values.
+///
+/// Input is read one token at a time and rotations are compiled as they are
+/// requested, so that memory does not grow with length of lines or number of
+/// repeats.
+struct RotationIter<R: BufRead> {
+    source: Source<R>,
+    /// Line of instruction being executed.
+    line_number: usize,
+    compiler: Compiler,
+}
+
+impl<R: BufRead> RotationIter<R> {
+    fn new(input: R, dial: Dial) -> Self {
+        Self {
+            source: Source::new(input),
+            line_number: 0,
+            compiler: Compiler::new(dial),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for RotationIter<R> {
+    type Item = anyhow::Result<Rotation>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(rotation) = self.compiler.next_rotation() {
+                return Some(
+                    rotation.map_err(|e| anyhow::anyhow!("line {}: {}", self.line_number, e)),
+                );
+            }
+            match self.compiler.compile_next(&mut self.source) {
+                Ok(true) => self.line_number = self.source.line_number(),
+                Ok(false) => return None,
+                Err(e) => {
+                    let line_number = self.source.line_number();
+                    return Some(Err(anyhow::anyhow!("line {}: {}", line_number, e)));
+                }
+            }
         }
-        let mut chars = line.chars();
-        let direction_char = chars.next().unwrap();
-        let direction = match direction_char {
-            'L' => Direction::Left,
-            'R' => Direction::Right,
-            _ => anyhow::bail!(
-                "line {}: invalid direction: {}",
-                line_number,
-                direction_char
-            ),
-        };
-        let steps_str = &line[1..];
-        let steps: usize = steps_str.parse().map_err(|e| {
-            anyhow::anyhow!("line {}: invalid step: {}: {}", line_number, steps_str, e)
-        })?;
-        instructions.push(Rotation { direction, steps });
-    }
-    Ok(instructions)
+    }
+}
+
+/// Parses a single `L<n>` or `R<n>` instruction.
+///
+/// Rotations being signed, `n` must be at most `i64::MAX` rather than
+/// `u64::MAX`, which is already over 10^18 clicks.
+fn parse_rotation(s: &str) -> anyhow::Result<Rotation> {
+    let mut chars = s.chars();
+    let sign = match chars.next() {
+        Some('L') => -1,
+        Some('R') => 1,
+        Some(c) => anyhow::bail!("invalid direction: {}", c),
+        None => anyhow::bail!("empty instruction"),
+    };
+    let steps_str = chars.as_str();
+    if steps_str.is_empty() || !steps_str.bytes().all(|b| b.is_ascii_digit()) {
+        anyhow::bail!("invalid step: {}", steps_str);
+    }
+    // Only overflow can make parsing fail now.
+    let steps: i64 = steps_str.parse().map_err(|_| {
+        anyhow::anyhow!(
+            "step out of range: {}: must be at most {}",
+            steps_str,
+            i64::MAX
+        )
+    })?;
+    Ok(Rotation {
+        steps: sign * steps,
+    })
 }
 
 /// Turns `dial` as specified in `instructions` and returns number of times it
 /// pointed to zero while being turned.
-fn execute_instructions(mut dial: Dial, instructions: &[Rotation]) -> usize {
+fn execute_instructions(mut dial: Dial, instructions: &[Rotation]) -> anyhow::Result<u64> {
     let mut number_of_zeroes = if dial.position() == 0 { 1 } else { 0 };
     for rotation in instructions {
-        number_of_zeroes += match rotation.direction {
-            Direction::Right => dial.turn_right(rotation.steps),
-            Direction::Left => dial.turn_left(rotation.steps),
-        };
+        number_of_zeroes =
+            add_zeroes(number_of_zeroes, dial.rotate(rotation.steps).nb_zero_passes)?;
+    }
+    Ok(number_of_zeroes)
+}
+
+/// Turns `dial` as specified in `instructions` `times` times in a row and
+/// returns number of times it pointed to zero.  Once dial comes back to a
+/// position it had at the start of an earlier repetition, remaining
+/// repetitions are counted without being simulated.
+fn execute_repeatedly(dial: Dial, instructions: &[Rotation], times: usize) -> anyhow::Result<u64> {
+    let iteration = cycle::iterate(
+        dial,
+        |&dial| {
+            let mut dial = dial;
+            execute_once(&mut dial, instructions);
+            dial
+        },
+        Some(times),
+    );
+    // Zeroes while executing repetition `i`, starting from generation `i`,
+    // for repetitions up to the end of the first cycle.
+    let nb_repetitions = iteration
+        .cycle
+        .map_or(times, |cycle| cycle.start + cycle.period);
+    let mut repeated = dial;
+    let nb_zeroes_per_repetition: Vec<u128> = (0..nb_repetitions)
+        .map(|_| execute_once(&mut repeated, instructions))
+        .collect();
+    let overflow = || anyhow::anyhow!("number of zeroes overflows: {} repetitions", times);
+    let add = |total: u128, n: u128| total.checked_add(n).ok_or_else(overflow);
+    let sum = |repetitions: std::ops::Range<usize>| -> anyhow::Result<u128> {
+        nb_zeroes_per_repetition[repetitions]
+            .iter()
+            .try_fold(0, |total, &n| add(total, n))
+    };
+    let mut total = if dial.position() == 0 { 1 } else { 0 };
+    match iteration.cycle {
+        None => total = add(total, sum(0..times)?)?,
+        Some(cycle) => {
+            let nb_cycles = ((times - cycle.start) / cycle.period) as u128;
+            let remainder = (times - cycle.start) % cycle.period;
+            let cycles = sum(cycle.start..cycle.start + cycle.period)?
+                .checked_mul(nb_cycles)
+                .ok_or_else(overflow)?;
+            total = add(total, sum(0..cycle.start)?)?;
+            total = add(total, cycles)?;
+            total = add(total, sum(cycle.start..cycle.start + remainder)?)?;
+        }
+    }
+    u64::try_from(total).map_err(|_| overflow())
+}
+
+/// Turns `dial` as specified in `instructions` and returns number of times it
+/// pointed to zero while being turned, not counting its initial position.
+fn execute_once(dial: &mut Dial, instructions: &[Rotation]) -> u128 {
+    instructions
+        .iter()
+        .map(|rotation| dial.rotate(rotation.steps).nb_zero_passes as u128)
+        .sum()
+}
+
+/// Returns `total + n`, failing instead of wrapping around.
+fn add_zeroes(total: u64, n: u64) -> anyhow::Result<u64> {
+    total
+        .checked_add(n)
+        .ok_or_else(|| anyhow::anyhow!("number of zeroes overflows: {} + {}", total, n))
+}
+
+/// Running totals while executing a stream of rotations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Progress {
+    nb_rotations: u64,
+    nb_zeroes: u64,
+}
+
+impl Progress {
+    /// Returns totals before turning `dial`.
+    fn new(dial: Dial) -> Self {
+        Self {
+            nb_rotations: 0,
+            nb_zeroes: if dial.position() == 0 { 1 } else { 0 },
+        }
     }
-    number_of_zeroes
+}
+
+/// Returns iterator that turns `dial` as specified by each rotation as it
+/// comes and yields running totals.  Iteration should stop at first error.
+fn execute_stream<I>(mut dial: Dial, rotations: I) -> impl Iterator<Item = anyhow::Result<Progress>>
+where
+    I: IntoIterator<Item = anyhow::Result<Rotation>>,
+{
+    let mut progress = Progress::new(dial);
+    rotations.into_iter().map(move |rotation| {
+        let rotation = rotation?;
+        progress.nb_rotations += 1;
+        progress.nb_zeroes = add_zeroes(
+            progress.nb_zeroes,
+            dial.rotate(rotation.steps).nb_zero_passes,
+        )?;
+        Ok(progress)
+    })
 }
 
 #[cfg(test)]
@@ -75,11 +366,15 @@ mod tests {
     use super::*;
     use std::io::Cursor;
 
+    fn dial() -> Dial {
+        Dial::new(dial::DEFAULT_SIZE, DEFAULT_START).unwrap()
+    }
+
     #[test]
     fn read_instructions_empty() {
         let data = "";
         let mut reader = Cursor::new(data.as_bytes());
-        let v = read_instructions(&mut reader).unwrap();
+        let v = read_instructions(&mut reader, dial()).unwrap();
         assert!(v.is_empty());
     }
 
@@ -87,9 +382,8 @@ mod tests {
     fn read_instructions_single() {
         let data = "R10\n";
         let mut reader = Cursor::new(data.as_bytes());
-        let v = read_instructions(&mut reader).unwrap();
+        let v = read_instructions(&mut reader, dial()).unwrap();
         assert_eq!(v.len(), 1);
-        assert!(matches!(v[0].direction, Direction::Right));
         assert_eq!(v[0].steps, 10);
     }
 
@@ -97,27 +391,158 @@ mod tests {
     fn read_instructions_multiple() {
         let data = "R5\nL15\nR100\n";
         let mut reader = Cursor::new(data.as_bytes());
-        let v = read_instructions(&mut reader).unwrap();
+        let v = read_instructions(&mut reader, dial()).unwrap();
         assert_eq!(v.len(), 3);
-        assert!(matches!(v[0].direction, Direction::Right));
         assert_eq!(v[0].steps, 5);
-        assert!(matches!(v[1].direction, Direction::Left));
-        assert_eq!(v[1].steps, 15);
-        assert!(matches!(v[2].direction, Direction::Right));
+        assert_eq!(v[1].steps, -15);
         assert_eq!(v[2].steps, 100);
     }
 
+    #[test]
+    fn read_instructions_step_out_of_range() {
+        let data = format!("R{}\nL{}\n", i64::MAX, i64::MAX);
+        let v = read_instructions(&mut Cursor::new(data), dial()).unwrap();
+        assert_eq!(
+            v,
+            vec![Rotation { steps: i64::MAX }, Rotation { steps: -i64::MAX }]
+        );
+
+        // Step counts are limited to i64::MAX, not u64::MAX.
+        for steps in [
+            "9223372036854775808",
+            "18446744073709551615",
+            "18446744073709551616",
+        ] {
+            let data = format!("R{}\n", steps);
+            let e = read_instructions(&mut Cursor::new(data), dial()).unwrap_err();
+            assert!(e.to_string().contains("out of range"), "{}", e);
+        }
+    }
+
+    #[test]
+    fn read_instructions_signed_step() {
+        let data = "R-5\n";
+        let mut reader = Cursor::new(data.as_bytes());
+        assert!(read_instructions(&mut reader, dial()).is_err());
+    }
+
+    #[test]
+    fn huge_rotations_count_exactly() {
+        let rotations = [Rotation { steps: i64::MAX }, Rotation { steps: i64::MAX }];
+        assert_eq!(
+            execute_instructions(dial(), &rotations).unwrap(),
+            2 * 92233720368547758
+        );
+    }
+
+    #[test]
+    fn zero_count_overflow_is_an_error() {
+        // Every click of a single-position dial points at zero.
+        let dial = Dial::new(1, 0).unwrap();
+        let rotations = [Rotation { steps: i64::MAX }; 3];
+        assert!(execute_instructions(dial, &rotations).is_err());
+        let stream = execute_stream(dial, rotations.map(Ok));
+        assert!(stream.collect::<anyhow::Result<Vec<_>>>().is_err());
+    }
+
+    #[test]
+    fn display_rotation() {
+        assert_eq!(Rotation { steps: 10 }.to_string(), "R10");
+        assert_eq!(Rotation { steps: -5 }.to_string(), "L5");
+    }
+
     #[test]
     fn read_instructions_invalid_direction() {
         let data = "X10\n";
         let mut reader = Cursor::new(data.as_bytes());
-        assert!(read_instructions(&mut reader).is_err());
+        assert!(read_instructions(&mut reader, dial()).is_err());
     }
 
     #[test]
     fn read_instructions_invalid_step() {
         let data = "R1a\n";
         let mut reader = Cursor::new(data.as_bytes());
-        assert!(read_instructions(&mut reader).is_err());
+        assert!(read_instructions(&mut reader, dial()).is_err());
+    }
+
+    #[test]
+    fn read_instructions_extended() {
+        let data = "R5, L15 # comment\n2*(R1)\nSET 0\n";
+        let mut reader = Cursor::new(data.as_bytes());
+        let v = read_instructions(&mut reader, dial()).unwrap();
+        let steps: Vec<i64> = v.iter().map(|r| r.steps).collect();
+        assert_eq!(steps, vec![5, -15, 1, 1, 58]);
+    }
+
+    #[test]
+    fn read_instructions_error_reports_line() {
+        let data = "R5\n2*(R1\n";
+        let mut reader = Cursor::new(data.as_bytes());
+        let e = read_instructions(&mut reader, dial()).unwrap_err();
+        assert!(e.to_string().starts_with("line 2:"));
+    }
+
+    #[test]
+    fn rotations_are_streamed() {
+        // Second line is malformed but first line is available before it is
+        // read.
+        let mut rotations = rotations_from_input(Cursor::new(b"R1,R2\nX\n"), dial());
+        assert_eq!(rotations.next().unwrap().unwrap(), Rotation { steps: 1 });
+        assert_eq!(rotations.next().unwrap().unwrap(), Rotation { steps: 2 });
+        assert!(rotations.next().unwrap().is_err());
+        // Same within a single line.
+        let mut rotations = rotations_from_input(Cursor::new(b"R1,R2,X"), dial());
+        assert_eq!(rotations.next().unwrap().unwrap(), Rotation { steps: 1 });
+        assert_eq!(rotations.next().unwrap().unwrap(), Rotation { steps: 2 });
+        let error = rotations.next().unwrap().unwrap_err();
+        assert!(error.to_string().starts_with("line 1:"));
+    }
+
+    #[test]
+    fn execute_stream_reports_running_totals() {
+        let rotations = [50, 100, 1].map(|steps| Ok(Rotation { steps }));
+        let progress: Vec<Progress> = execute_stream(dial(), rotations)
+            .collect::<anyhow::Result<_>>()
+            .unwrap();
+        let totals: Vec<(u64, u64)> = progress
+            .iter()
+            .map(|p| (p.nb_rotations, p.nb_zeroes))
+            .collect();
+        assert_eq!(totals, vec![(1, 1), (2, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn repeated_instructions() {
+        let data = "L68\nL30\nR48\nL5\nR60\nL55\nL1\nL99\nR14\nL82\n";
+        let instructions = read_instructions(&mut Cursor::new(data), dial()).unwrap();
+        for times in [0, 1, 2, 49, 50, 51, 77] {
+            let repeated: Vec<_> = instructions.repeat(times);
+            assert_eq!(
+                execute_repeatedly(dial(), &instructions, times).unwrap(),
+                execute_instructions(dial(), &repeated).unwrap()
+            );
+        }
+        // Each repetition turns dial 18 clicks left, back to start after 50.
+        let per_cycle = execute_instructions(dial(), &instructions.repeat(50)).unwrap();
+        assert_eq!(
+            execute_repeatedly(dial(), &instructions, 50_000_000_000).unwrap(),
+            1_000_000_000 * per_cycle
+        );
+        let huge = [Rotation { steps: i64::MAX }];
+        assert!(execute_repeatedly(dial(), &huge, 10).is_ok());
+        assert!(execute_repeatedly(dial(), &huge, 1_000_000_000_000).is_err());
+        // Too many zeroes even for intermediate 128-bit sums.
+        let tiny = Dial::new(1, 0).unwrap();
+        let e = execute_repeatedly(tiny, &huge.repeat(3), usize::MAX).unwrap_err();
+        assert!(e.to_string().starts_with("number of zeroes overflows"));
+    }
+
+    #[test]
+    fn solve_sample() {
+        let data = "L68\nL30\nR48\nL5\nR60\nL55\nL1\nL99\nR14\nL82\n";
+        let mut nb_reports = 0;
+        let answer = solve_for(Cursor::new(data), dial(), |_| nb_reports += 1).unwrap();
+        assert_eq!(answer, 6);
+        assert_eq!(nb_reports, 10);
     }
 }
@@ -0,0 +1,277 @@
+use std::io::Write;
+
+use crate::Rotation;
+use crate::dial::Dial;
+
+/// Largest dial accepted by `Statistics`, to bound memory use.
+const MAX_SIZE: i64 = 1 << 24;
+
+/// Width of the longest histogram bar.
+const BAR_WIDTH: u64 = 50;
+
+/// Where a dial spent its time while executing rotations.
+#[derive(Debug, Clone)]
+pub struct Statistics {
+    dial: Dial,
+    /// For each position, number of rotations that stopped on it.
+    nb_landings: Vec<u64>,
+    /// Number of full turns made without stopping, which passed over all
+    /// positions.
+    nb_laps: u64,
+    /// Difference array of passes over each position during partial turns.
+    partial_passes: Vec<i64>,
+    /// Number of clicks since dial last pointed at zero.
+    current_run: u64,
+    longest_run: u64,
+    displacement: i128,
+}
+
+impl Statistics {
+    /// Creates empty statistics for rotations to be executed on `dial`.
+    pub fn new(dial: Dial) -> anyhow::Result<Self> {
+        if dial.size() > MAX_SIZE {
+            anyhow::bail!(
+                "dial too large for statistics: {} positions, at most {}",
+                dial.size(),
+                MAX_SIZE
+            );
+        }
+        let size = dial.size() as usize;
+        Ok(Self {
+            dial,
+            nb_landings: vec![0; size],
+            nb_laps: 0,
+            partial_passes: vec![0; size + 1],
+            current_run: 0,
+            longest_run: 0,
+            displacement: 0,
+        })
+    }
+
+    /// Turns dial as specified by `rotation` and records where it went.
+    pub fn record(&mut self, rotation: Rotation) -> anyhow::Result<()> {
+        let n = rotation.steps.unsigned_abs();
+        if n == 0 {
+            return Ok(());
+        }
+        let size = self.dial.size() as u64;
+        let start = self.dial.position();
+        let clicks_to_zero = self.dial.clicks_to_zero(rotation.steps) as u64;
+        let outcome = self.dial.rotate(rotation.steps);
+
+        self.nb_landings[outcome.position as usize] += 1;
+        self.displacement += rotation.steps as i128;
+
+        // The dial passes over positions pointed at before the last click.
+        let nb_passed = n - 1;
+        self.nb_laps = self
+            .nb_laps
+            .checked_add(nb_passed / size)
+            .ok_or_else(|| anyhow::anyhow!("number of passes overflows"))?;
+        let remainder = (nb_passed % size) as i64;
+        if remainder > 0 {
+            let first = if rotation.steps > 0 {
+                start + 1
+            } else {
+                start - remainder
+            };
+            self.add_partial_pass(first.rem_euclid(size as i64) as usize, remainder as usize);
+        }
+
+        if n < clicks_to_zero {
+            self.current_run = self
+                .current_run
+                .checked_add(n)
+                .ok_or_else(|| anyhow::anyhow!("run length overflows"))?;
+        } else {
+            let run_before_zero = self
+                .current_run
+                .checked_add(clicks_to_zero - 1)
+                .ok_or_else(|| anyhow::anyhow!("run length overflows"))?;
+            self.longest_run = self.longest_run.max(run_before_zero);
+            if outcome.nb_zero_passes >= 2 {
+                self.longest_run = self.longest_run.max(size - 1);
+            }
+            let last_zero = clicks_to_zero + (outcome.nb_zero_passes - 1) * size;
+            self.current_run = n - last_zero;
+        }
+        self.longest_run = self.longest_run.max(self.current_run);
+        Ok(())
+    }
+
+    /// Records one pass over `len` positions starting at `first` and
+    /// wrapping around.
+    fn add_partial_pass(&mut self, first: usize, len: usize) {
+        let size = self.nb_landings.len();
+        let end = first + len;
+        self.partial_passes[first] += 1;
+        if end <= size {
+            self.partial_passes[end] -= 1;
+        } else {
+            self.partial_passes[size] -= 1;
+            self.partial_passes[0] += 1;
+            self.partial_passes[end - size] -= 1;
+        }
+    }
+
+    /// Returns, for each position, number of rotations that stopped on it.
+    pub fn nb_landings(&self) -> &[u64] {
+        &self.nb_landings
+    }
+
+    /// Returns, for each position, number of times the dial pointed at it
+    /// without stopping.
+    pub fn nb_passes(&self) -> anyhow::Result<Vec<u64>> {
+        let mut partial = 0;
+        self.partial_passes[..self.nb_landings.len()]
+            .iter()
+            .map(|d| {
+                partial += d;
+                self.nb_laps
+                    .checked_add(partial as u64)
+                    .ok_or_else(|| anyhow::anyhow!("number of passes overflows"))
+            })
+            .collect()
+    }
+
+    /// Returns largest number of consecutive clicks without pointing at zero.
+    pub fn longest_run_without_zero(&self) -> u64 {
+        self.longest_run
+    }
+
+    /// Returns net number of clicks turned, positive to the right.
+    pub fn displacement(&self) -> i128 {
+        self.displacement
+    }
+
+    /// Prints histogram of positions followed by summary.
+    pub fn write_report<W: Write>(&self, mut output: W) -> anyhow::Result<()> {
+        let nb_passes = self.nb_passes()?;
+        let nb_landings = self.nb_landings();
+        let max_visits = nb_landings
+            .iter()
+            .zip(&nb_passes)
+            .map(|(l, p)| l.saturating_add(*p))
+            .max()
+            .unwrap_or(0);
+        writeln!(output, "{:>8} {:>8} {:>12}", "position", "landed", "passed")?;
+        for (pos, (landed, passed)) in nb_landings.iter().zip(&nb_passes).enumerate() {
+            let visits = landed.saturating_add(*passed);
+            let bar_len = if max_visits == 0 {
+                0
+            } else {
+                (visits as u128 * BAR_WIDTH as u128).div_ceil(max_visits as u128) as usize
+            };
+            writeln!(
+                output,
+                "{:>8} {:>8} {:>12} {}",
+                pos,
+                landed,
+                passed,
+                "#".repeat(bar_len)
+            )?;
+        }
+        writeln!(
+            output,
+            "longest run without zero: {} clicks",
+            self.longest_run_without_zero()
+        )?;
+        writeln!(output, "net displacement: {} clicks", self.displacement())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(size: i64, start: i64, steps: &[i64]) -> Statistics {
+        let mut stats = Statistics::new(Dial::new(size, start).unwrap()).unwrap();
+        for &steps in steps {
+            stats.record(Rotation { steps }).unwrap();
+        }
+        stats
+    }
+
+    #[test]
+    fn landings() {
+        let stats = record(10, 5, &[2, -4, 0, 10]);
+        assert_eq!(stats.nb_landings(), &[0, 0, 0, 2, 0, 0, 0, 1, 0, 0]);
+    }
+
+    #[test]
+    fn passes_without_wrapping() {
+        let stats = record(10, 5, &[3, -2]);
+        assert_eq!(
+            stats.nb_passes().unwrap(),
+            vec![0, 0, 0, 0, 0, 0, 1, 2, 0, 0]
+        );
+    }
+
+    #[test]
+    fn passes_wrapping_around() {
+        let stats = record(10, 8, &[4]);
+        assert_eq!(
+            stats.nb_passes().unwrap(),
+            vec![1, 1, 0, 0, 0, 0, 0, 0, 0, 1]
+        );
+        let stats = record(10, 1, &[-4]);
+        assert_eq!(
+            stats.nb_passes().unwrap(),
+            vec![1, 0, 0, 0, 0, 0, 0, 0, 1, 1]
+        );
+    }
+
+    #[test]
+    fn passes_with_full_laps() {
+        let stats = record(4, 0, &[10]);
+        assert_eq!(stats.nb_passes().unwrap(), vec![2, 3, 2, 2]);
+        assert_eq!(stats.nb_landings(), &[0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn longest_run() {
+        // Runs of 2 clicks before zero, then 99, 10 and 2.
+        let stats = record(100, 3, &[-3, -99, -1, 10, -10, -2]);
+        assert_eq!(stats.longest_run_without_zero(), 99);
+        let stats = record(100, 50, &[-10, 20, -30]);
+        assert_eq!(stats.longest_run_without_zero(), 60);
+        let stats = record(10, 0, &[35]);
+        assert_eq!(stats.longest_run_without_zero(), 9);
+    }
+
+    #[test]
+    fn displacement() {
+        let stats = record(100, 50, &[-68, -30, 48, i64::MAX, i64::MIN]);
+        assert_eq!(stats.displacement(), -51);
+    }
+
+    #[test]
+    fn zero_visits_match_zero_count() {
+        let steps = [-68, -30, 48, -5, 60, -55, -1, -99, 14, -82];
+        let stats = record(100, 50, &steps);
+        let zero_visits = stats.nb_landings()[0] + stats.nb_passes().unwrap()[0];
+        assert_eq!(zero_visits, 6);
+    }
+
+    #[test]
+    fn rejects_huge_dial() {
+        assert!(Statistics::new(Dial::new(i64::MAX, 0).unwrap()).is_err());
+    }
+
+    #[test]
+    fn report() {
+        let stats = record(2, 0, &[1]);
+        let mut output = Vec::new();
+        stats.write_report(&mut output).unwrap();
+        let expected = format!(
+            "position   landed       passed\n       \
+             0        0            0 \n       \
+             1        1            0 {}\n\
+             longest run without zero: 1 clicks\n\
+             net displacement: 1 clicks\n",
+            "#".repeat(BAR_WIDTH as usize)
+        );
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+}
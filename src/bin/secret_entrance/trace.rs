@@ -0,0 +1,188 @@
+use std::io::Write;
+use std::str::FromStr;
+
+use crate::Rotation;
+use crate::dial::Dial;
+
+/// What happened to the dial while executing a single rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// 1-based index of rotation in instruction list.
+    pub index: usize,
+    pub steps: i64,
+    pub start: i64,
+    pub end: i64,
+    pub nb_zero_passes: u64,
+    pub landed_on_zero: bool,
+}
+
+/// Turns `dial` as specified in `instructions` and records what happened at
+/// each rotation.
+pub fn trace_instructions(mut dial: Dial, instructions: &[Rotation]) -> Vec<TraceEntry> {
+    let mut trace = Vec::with_capacity(instructions.len());
+    for (i, rotation) in instructions.iter().enumerate() {
+        let start = dial.position();
+        let outcome = dial.rotate(rotation.steps);
+        trace.push(TraceEntry {
+            index: i + 1,
+            steps: rotation.steps,
+            start,
+            end: outcome.position,
+            nb_zero_passes: outcome.nb_zero_passes,
+            landed_on_zero: outcome.landed_on_zero,
+        });
+    }
+    trace
+}
+
+/// How to print a trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    Csv,
+    Json,
+    Table,
+}
+
+impl FromStr for TraceFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            "table" => Ok(Self::Table),
+            _ => anyhow::bail!("unknown trace format: {} (expected csv, json or table)", s),
+        }
+    }
+}
+
+pub fn write_trace<W: Write>(
+    mut output: W,
+    trace: &[TraceEntry],
+    format: TraceFormat,
+) -> std::io::Result<()> {
+    match format {
+        TraceFormat::Csv => {
+            writeln!(
+                output,
+                "index,rotation,start,end,zero_passes,landed_on_zero"
+            )?;
+            for e in trace {
+                writeln!(
+                    output,
+                    "{},{},{},{},{},{}",
+                    e.index,
+                    Rotation { steps: e.steps },
+                    e.start,
+                    e.end,
+                    e.nb_zero_passes,
+                    e.landed_on_zero
+                )?;
+            }
+        }
+        TraceFormat::Json => {
+            write!(output, "[")?;
+            for (i, e) in trace.iter().enumerate() {
+                let separator = if i == 0 { "" } else { "," };
+                write!(
+                    output,
+                    "{}\n  {{\"index\": {}, \"rotation\": \"{}\", \"start\": {}, \"end\": {}, \
+                     \"zero_passes\": {}, \"landed_on_zero\": {}}}",
+                    separator,
+                    e.index,
+                    Rotation { steps: e.steps },
+                    e.start,
+                    e.end,
+                    e.nb_zero_passes,
+                    e.landed_on_zero
+                )?;
+            }
+            writeln!(output, "\n]")?;
+        }
+        TraceFormat::Table => {
+            writeln!(
+                output,
+                "{:>6} {:>8} {:>6} {:>6} {:>11} {:>7}",
+                "index", "rotation", "start", "end", "zero passes", "landed"
+            )?;
+            for e in trace {
+                writeln!(
+                    output,
+                    "{:>6} {:>8} {:>6} {:>6} {:>11} {:>7}",
+                    e.index,
+                    Rotation { steps: e.steps }.to_string(),
+                    e.start,
+                    e.end,
+                    e.nb_zero_passes,
+                    if e.landed_on_zero { "yes" } else { "" }
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_trace() -> Vec<TraceEntry> {
+        let dial = Dial::new(100, 50).unwrap();
+        trace_instructions(dial, &[Rotation { steps: -68 }, Rotation { steps: 82 }])
+    }
+
+    #[test]
+    fn trace_records_each_rotation() {
+        assert_eq!(
+            sample_trace(),
+            vec![
+                TraceEntry {
+                    index: 1,
+                    steps: -68,
+                    start: 50,
+                    end: 82,
+                    nb_zero_passes: 1,
+                    landed_on_zero: false,
+                },
+                TraceEntry {
+                    index: 2,
+                    steps: 82,
+                    start: 82,
+                    end: 64,
+                    nb_zero_passes: 1,
+                    landed_on_zero: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn csv() {
+        let mut output = Vec::new();
+        write_trace(&mut output, &sample_trace(), TraceFormat::Csv).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "index,rotation,start,end,zero_passes,landed_on_zero\n\
+             1,L68,50,82,1,false\n\
+             2,R82,82,64,1,false\n"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let mut output = Vec::new();
+        write_trace(&mut output, &sample_trace()[..1], TraceFormat::Json).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[\n  {\"index\": 1, \"rotation\": \"L68\", \"start\": 50, \"end\": 82, \
+             \"zero_passes\": 1, \"landed_on_zero\": false}\n]\n"
+        );
+    }
+
+    #[test]
+    fn parse_format() {
+        assert!(matches!("csv".parse(), Ok(TraceFormat::Csv)));
+        assert!(matches!("table".parse(), Ok(TraceFormat::Table)));
+        assert!("xml".parse::<TraceFormat>().is_err());
+    }
+}
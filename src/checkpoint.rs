@@ -0,0 +1,313 @@
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::CommandLine;
+
+/// Default number of seconds between checkpoints.
+const DEFAULT_INTERVAL: u64 = 60;
+
+/// Identifies input and options a computation was started with, so that its
+/// checkpoints are never resumed by a different one.  Computed with 64-bit
+/// FNV-1a, which unlike `DefaultHasher` is the same from one build to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fingerprint(u64);
+
+impl Default for Fingerprint {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fingerprint {
+    /// Returns fingerprint of everything left in `input`, read one buffer at a
+    /// time.
+    pub fn of_input<R: BufRead>(mut input: R) -> anyhow::Result<Self> {
+        let mut fingerprint = Self::default();
+        loop {
+            let buffer = input.fill_buf()?;
+            if buffer.is_empty() {
+                return Ok(fingerprint);
+            }
+            fingerprint.update(buffer);
+            let n = buffer.len();
+            input.consume(n);
+        }
+    }
+
+    /// Returns fingerprint also covering `data`, such as an option value.
+    pub fn with(mut self, data: impl AsRef<[u8]>) -> Self {
+        let data = data.as_ref();
+        // Length first, so that moving bytes between parts changes result.
+        self.update(&(data.len() as u64).to_le_bytes());
+        self.update(data);
+        self
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for &b in data {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(0x100_0000_01b3);
+        }
+    }
+}
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl FromStr for Fingerprint {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u64::from_str_radix(s, 16).map(Self)
+    }
+}
+
+/// Solver state that can be saved to and restored from a checkpoint file.
+pub trait Checkpoint: Sized {
+    fn save<W: Write>(&self, output: W) -> anyhow::Result<()>;
+
+    fn restore<R: BufRead>(input: R) -> anyhow::Result<Self>;
+}
+
+/// Periodically saves solver state to a file, so that an interrupted run can
+/// be resumed from it.
+#[derive(Debug)]
+pub struct Checkpointer {
+    path: PathBuf,
+    interval: Duration,
+    resume: bool,
+    fingerprint: Fingerprint,
+    last_save: Instant,
+}
+
+impl Checkpointer {
+    /// Creates checkpointer saving to `path` at most once per `interval`, and
+    /// resuming from `path` if `resume` is set.
+    pub fn new(path: PathBuf, interval: Duration, resume: bool) -> Self {
+        Self {
+            path,
+            interval,
+            resume,
+            fingerprint: Fingerprint::default(),
+            last_save: Instant::now(),
+        }
+    }
+
+    /// Builds checkpointer from `--checkpoint=path`, `--checkpoint-interval`
+    /// (in seconds) and `--resume` options, or returns `None` if there is no
+    /// `--checkpoint` option.
+    pub fn from_command_line(cmd_line: &CommandLine) -> anyhow::Result<Option<Self>> {
+        let resume = cmd_line.flag("resume");
+        let Some(path) = cmd_line.option("checkpoint")? else {
+            if resume {
+                anyhow::bail!("--resume requires --checkpoint");
+            }
+            return Ok(None);
+        };
+        let interval = cmd_line
+            .option("checkpoint-interval")?
+            .unwrap_or(DEFAULT_INTERVAL);
+        Ok(Some(Self::new(path, Duration::from_secs(interval), resume)))
+    }
+
+    /// Returns state saved by previous run when resuming, or else state
+    /// returned by `start`.  Checkpoints are tagged with `fingerprint`, and
+    /// resuming from one saved with another fingerprint fails.
+    pub fn start<C: Checkpoint>(
+        &mut self,
+        fingerprint: Fingerprint,
+        start: impl FnOnce() -> anyhow::Result<C>,
+    ) -> anyhow::Result<C> {
+        self.fingerprint = fingerprint;
+        self.last_save = Instant::now();
+        if !self.resume {
+            return start();
+        }
+        let file = File::open(&self.path)
+            .map_err(|e| anyhow::anyhow!("failed to open {}: {}", self.path.display(), e))?;
+        let mut input = BufReader::new(file);
+        let invalid = |e: anyhow::Error| {
+            anyhow::anyhow!("invalid checkpoint: {}: {}", self.path.display(), e)
+        };
+        let saved: Fingerprint = read_field(&mut input, "fingerprint").map_err(invalid)?;
+        if saved != fingerprint {
+            anyhow::bail!(
+                "checkpoint {} was saved for another input or other options",
+                self.path.display()
+            );
+        }
+        C::restore(input).map_err(invalid)
+    }
+
+    /// Saves `state` if last save is older than interval.  Returns true if
+    /// state was saved.
+    pub fn maybe_save<C: Checkpoint>(&mut self, state: &C) -> anyhow::Result<bool> {
+        if self.last_save.elapsed() < self.interval {
+            return Ok(false);
+        }
+        self.save(state)?;
+        Ok(true)
+    }
+
+    /// Saves `state`, replacing previous checkpoint only once it is complete.
+    pub fn save<C: Checkpoint>(&mut self, state: &C) -> anyhow::Result<()> {
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        let temporary = PathBuf::from(temporary);
+        let file = File::create(&temporary)
+            .map_err(|e| anyhow::anyhow!("failed to create {}: {}", temporary.display(), e))?;
+        let mut output = BufWriter::new(file);
+        write_field(&mut output, "fingerprint", self.fingerprint)?;
+        state.save(&mut output)?;
+        output.into_inner()?.sync_all()?;
+        fs::rename(&temporary, &self.path)
+            .map_err(|e| anyhow::anyhow!("failed to write {}: {}", self.path.display(), e))?;
+        self.last_save = Instant::now();
+        Ok(())
+    }
+
+    /// Removes checkpoint once computation is over.
+    pub fn finish(self) -> anyhow::Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(anyhow::anyhow!(
+                "failed to remove {}: {}",
+                self.path.display(),
+                e
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Writes `name value` line of a checkpoint.
+pub fn write_field<W: Write, T: Display>(
+    mut output: W,
+    name: &str,
+    value: T,
+) -> anyhow::Result<()> {
+    writeln!(output, "{} {}", name, value)?;
+    Ok(())
+}
+
+/// Reads `name value` line of a checkpoint, as written by `write_field`.
+pub fn read_field<R: BufRead, T>(mut input: R, name: &str) -> anyhow::Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    let value = line
+        .trim_end()
+        .strip_prefix(name)
+        .and_then(|rest| rest.strip_prefix(' '))
+        .ok_or_else(|| anyhow::anyhow!("expected {}, found: {:?}", name, line))?;
+    value
+        .parse()
+        .map_err(|e| anyhow::anyhow!("invalid {}: {}: {}", name, value, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Counter(u64);
+
+    impl Checkpoint for Counter {
+        fn save<W: Write>(&self, output: W) -> anyhow::Result<()> {
+            write_field(output, "count", self.0)
+        }
+
+        fn restore<R: BufRead>(input: R) -> anyhow::Result<Self> {
+            Ok(Self(read_field(input, "count")?))
+        }
+    }
+
+    fn temporary_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("checkpoint-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn fields() -> anyhow::Result<()> {
+        let mut output = Vec::new();
+        write_field(&mut output, "sum", 42)?;
+        write_field(&mut output, "index", 3)?;
+        let mut input = Cursor::new(output);
+        assert_eq!(read_field::<_, u64>(&mut input, "sum")?, 42);
+        assert!(read_field::<_, u64>(&mut input, "sum").is_err());
+        assert!(read_field::<_, u64>(Cursor::new("sum x\n"), "sum").is_err());
+        assert!(read_field::<_, u64>(Cursor::new(""), "sum").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn save_and_resume() -> anyhow::Result<()> {
+        let path = temporary_path("resume");
+        let fingerprint = Fingerprint::default().with("input");
+        let mut checkpointer = Checkpointer::new(path.clone(), Duration::ZERO, false);
+        assert_eq!(
+            checkpointer.start(fingerprint, || Ok(Counter(0)))?,
+            Counter(0)
+        );
+        assert!(checkpointer.maybe_save(&Counter(7))?);
+
+        let mut other = Checkpointer::new(path.clone(), Duration::MAX, true);
+        let e = other
+            .start(Fingerprint::default().with("other"), || Ok(Counter(0)))
+            .unwrap_err();
+        assert!(e.to_string().contains("another input"));
+
+        let mut resumed = Checkpointer::new(path.clone(), Duration::MAX, true);
+        assert_eq!(resumed.start(fingerprint, || Ok(Counter(0)))?, Counter(7));
+        assert!(!resumed.maybe_save(&Counter(8))?);
+        resumed.finish()?;
+        assert!(!path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn resume_without_checkpoint() {
+        let path = temporary_path("missing");
+        let mut checkpointer = Checkpointer::new(path, Duration::ZERO, true);
+        assert!(
+            checkpointer
+                .start(Fingerprint::default(), || Ok(Counter(0)))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn fingerprints() -> anyhow::Result<()> {
+        let input = Fingerprint::of_input(Cursor::new("abc"))?;
+        assert_eq!(input.to_string(), "e71fa2190541574b");
+        assert_eq!(input.to_string().parse::<Fingerprint>()?, input);
+        assert_eq!(
+            Fingerprint::of_input(Cursor::new(""))?,
+            Fingerprint::default()
+        );
+        assert_ne!(input.with("ab").with("c"), input.with("a").with("bc"));
+        Ok(())
+    }
+
+    #[test]
+    fn command_line() -> anyhow::Result<()> {
+        let parse = |args: &[&str]| {
+            Checkpointer::from_command_line(&CommandLine::parse(args.iter().map(|a| a.to_string())))
+        };
+        assert!(parse(&[])?.is_none());
+        assert!(parse(&["--resume"]).is_err());
+        let checkpointer = parse(&["--checkpoint=state", "--checkpoint-interval=5"])?.unwrap();
+        assert_eq!(checkpointer.path, PathBuf::from("state"));
+        assert_eq!(checkpointer.interval, Duration::from_secs(5));
+        assert!(!checkpointer.resume);
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+use crate::grid::Grid;
+use crate::neighborhood::{Boundary, Neighborhood};
+
+/// A set of connected tiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cluster {
+    pub size: usize,
+    /// First tile of cluster in row-major order.
+    pub first: (usize, usize),
+    /// Top-left corner of bounding box.
+    pub min: (usize, usize),
+    /// Bottom-right corner of bounding box, inclusive.
+    pub max: (usize, usize),
+}
+
+/// Tiles of a grid partitioned into clusters, numbered in row-major order of
+/// their first tile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Components {
+    labels: Grid<Option<usize>>,
+    clusters: Vec<Cluster>,
+}
+
+impl Components {
+    /// Returns cluster containing tile at `(x, y)`, or `None` if tile does not
+    /// belong to any.
+    pub fn label(&self, x: usize, y: usize) -> Option<usize> {
+        self.labels[(x, y)]
+    }
+
+    pub fn clusters(&self) -> &[Cluster] {
+        &self.clusters
+    }
+}
+
+impl<T> Grid<T> {
+    /// Returns clusters of tiles for which `belongs` is true, two such tiles
+    /// being connected if either is in the neighborhood of the other.
+    pub fn components(
+        &self,
+        neighborhood: &Neighborhood,
+        boundary: &Boundary<T>,
+        belongs: impl Fn(&T) -> bool,
+    ) -> Components {
+        let offset = |(x, y): (usize, usize)| y * self.width() + x;
+        let mut sets = DisjointSets::new(self.width() * self.height());
+        for p in self.positions() {
+            if belongs(&self[p]) {
+                for q in self.neighborhood_positions(p.0, p.1, neighborhood, boundary) {
+                    if belongs(&self[q]) {
+                        sets.union(offset(p), offset(q));
+                    }
+                }
+            }
+        }
+
+        let mut labels = Grid::new(self.width(), self.height(), None);
+        let mut root_labels = vec![None; self.width() * self.height()];
+        let mut clusters: Vec<Cluster> = Vec::new();
+        for (x, y) in self.positions() {
+            if !belongs(&self[(x, y)]) {
+                continue;
+            }
+            let root = sets.find(offset((x, y)));
+            let label = *root_labels[root].get_or_insert_with(|| {
+                clusters.push(Cluster {
+                    size: 0,
+                    first: (x, y),
+                    min: (x, y),
+                    max: (x, y),
+                });
+                clusters.len() - 1
+            });
+            let cluster = &mut clusters[label];
+            cluster.size += 1;
+            cluster.min = (cluster.min.0.min(x), cluster.min.1.min(y));
+            cluster.max = (cluster.max.0.max(x), cluster.max.1.max(y));
+            labels[(x, y)] = Some(label);
+        }
+        Components { labels, clusters }
+    }
+}
+
+/// Union-find forest over `0..n`.
+struct DisjointSets {
+    parents: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(n: usize) -> Self {
+        Self {
+            parents: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            // Path halving.
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+        i
+    }
+
+    fn union(&mut self, i: usize, j: usize) {
+        let (i, j) = (self.find(i), self.find(j));
+        // Keep lowest index as root so that trees stay shallow in row-major
+        // scans.
+        if i < j {
+            self.parents[j] = i;
+        } else {
+            self.parents[i] = j;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Tile;
+    use indoc::indoc;
+    use std::io::Cursor;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Cell(bool);
+
+    impl Tile for Cell {
+        fn from_char(c: char) -> Option<Self> {
+            match c {
+                '#' => Some(Cell(true)),
+                '.' => Some(Cell(false)),
+                _ => None,
+            }
+        }
+
+        fn to_char(&self) -> char {
+            if self.0 { '#' } else { '.' }
+        }
+    }
+
+    fn grid() -> Grid<Cell> {
+        Grid::load(Cursor::new(indoc! {b"
+            ##..#
+            #..#.
+            ...#.
+            #....
+        "}))
+        .unwrap()
+    }
+
+    fn components(neighborhood: Neighborhood, boundary: Boundary<Cell>) -> Components {
+        grid().components(&neighborhood, &boundary, |cell| cell.0)
+    }
+
+    #[test]
+    fn von_neumann() {
+        let components = components(Neighborhood::von_neumann(), Boundary::Clip);
+        let sizes: Vec<_> = components.clusters().iter().map(|c| c.size).collect();
+        assert_eq!(sizes, vec![3, 1, 2, 1]);
+        assert_eq!(components.label(0, 1), Some(0));
+        assert_eq!(components.label(4, 0), Some(1));
+        assert_eq!(components.label(3, 2), Some(2));
+        assert_eq!(components.label(2, 2), None);
+        assert_eq!(
+            components.clusters()[2],
+            Cluster {
+                size: 2,
+                first: (3, 1),
+                min: (3, 1),
+                max: (3, 2),
+            }
+        );
+    }
+
+    #[test]
+    fn moore() {
+        let components = components(Neighborhood::moore(), Boundary::Clip);
+        let sizes: Vec<_> = components.clusters().iter().map(|c| c.size).collect();
+        assert_eq!(sizes, vec![3, 3, 1]);
+        assert_eq!(components.clusters()[1].min, (3, 0));
+        assert_eq!(components.clusters()[1].max, (4, 2));
+    }
+
+    #[test]
+    fn wrap() {
+        // Top-left cluster joins bottom-left and top-right tiles.
+        let components = components(Neighborhood::von_neumann(), Boundary::Wrap);
+        let sizes: Vec<_> = components.clusters().iter().map(|c| c.size).collect();
+        assert_eq!(sizes, vec![5, 2]);
+        assert_eq!(components.clusters()[0].max, (4, 3));
+    }
+
+    #[test]
+    fn asymmetric_neighborhood() {
+        // Only right neighbors, still connecting both ways.
+        let grid = Grid::load(Cursor::new(b"##.#\n")).unwrap();
+        let components = grid.components(
+            &Neighborhood::custom(vec![(1, 0)]),
+            &Boundary::Clip,
+            |cell: &Cell| cell.0,
+        );
+        assert_eq!(components.clusters().len(), 2);
+        assert_eq!(components.label(1, 0), Some(0));
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Generations from which a sequence of states repeats itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    /// First generation of the cycle.
+    pub start: usize,
+    /// Number of generations after which states repeat, 1 for a fixed point.
+    pub period: usize,
+}
+
+impl Cycle {
+    pub fn is_fixed_point(&self) -> bool {
+        self.period == 1
+    }
+
+    /// Returns earliest generation having same state as `generation`.
+    pub fn earliest(&self, generation: usize) -> usize {
+        if generation < self.start {
+            generation
+        } else {
+            self.start + (generation - self.start) % self.period
+        }
+    }
+}
+
+/// Where iterating a step function stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Iteration<S> {
+    /// State at `generation`.
+    pub state: S,
+    pub generation: usize,
+    /// Cycle found when state came back to an earlier one.
+    pub cycle: Option<Cycle>,
+}
+
+/// Applies `step` to successive states from `initial` until a state repeats,
+/// or until `max_generations` if given.
+///
+/// Only hashes of earlier states are kept.  When one matches, states are
+/// replayed from `initial` to compare them exactly, so `step` must always
+/// return the same state from the same state.
+pub fn iterate<S: Clone + Eq + Hash>(
+    initial: S,
+    mut step: impl FnMut(&S) -> S,
+    max_generations: Option<usize>,
+) -> Iteration<S> {
+    // Generations of states having each hash.
+    let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut state = initial.clone();
+    let mut generation = 0;
+    loop {
+        let hash = hash_of(&state);
+        if let Some(generations) = seen.get(&hash)
+            && let Some(start) = find_state(&initial, &mut step, generations, &state)
+        {
+            let cycle = Cycle {
+                start,
+                period: generation - start,
+            };
+            return Iteration {
+                state,
+                generation,
+                cycle: Some(cycle),
+            };
+        }
+        if max_generations == Some(generation) {
+            return Iteration {
+                state,
+                generation,
+                cycle: None,
+            };
+        }
+        seen.entry(hash).or_default().push(generation);
+        state = step(&state);
+        generation += 1;
+    }
+}
+
+fn hash_of<S: Hash>(state: &S) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns first of increasing `generations` whose state, replayed from
+/// `initial`, is `state`.
+fn find_state<S: Clone + Eq>(
+    initial: &S,
+    step: &mut impl FnMut(&S) -> S,
+    generations: &[usize],
+    state: &S,
+) -> Option<usize> {
+    let mut replayed = initial.clone();
+    let mut replayed_generation = 0;
+    for &generation in generations {
+        while replayed_generation < generation {
+            replayed = step(&replayed);
+            replayed_generation += 1;
+        }
+        if replayed == *state {
+            return Some(generation);
+        }
+    }
+    None
+}
+
+/// Returns state reached from `initial` after `generation` applications of
+/// `step`, skipping whole cycles once one is found.
+pub fn fast_forward<S: Clone + Eq + Hash>(
+    initial: S,
+    mut step: impl FnMut(&S) -> S,
+    generation: usize,
+) -> Iteration<S> {
+    let mut iteration = iterate(initial, &mut step, Some(generation));
+    if let Some(cycle) = iteration.cycle {
+        // Detected at `start + period`, which has same state as `start`.
+        for _ in cycle.start..cycle.earliest(generation) {
+            iteration.state = step(&iteration.state);
+        }
+        iteration.generation = generation;
+    }
+    iteration
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Steps of `x -> x * x + 3 mod 41` from 2, entering a cycle of length 8
+    /// after 4 steps.
+    fn square(x: &u64) -> u64 {
+        (x * x + 3) % 41
+    }
+
+    fn naive_state_at(generation: usize) -> u64 {
+        (0..generation).fold(2, |x, _| square(&x))
+    }
+
+    #[test]
+    fn cycle() {
+        let iteration = iterate(2, square, None);
+        let cycle = iteration.cycle.unwrap();
+        assert_eq!(
+            cycle,
+            Cycle {
+                start: 4,
+                period: 8
+            }
+        );
+        assert_eq!(iteration.generation, 12);
+        assert_eq!(iteration.state, naive_state_at(4));
+        assert_eq!(cycle.earliest(1), 1);
+        assert_eq!(cycle.earliest(15), 7);
+        assert!(!cycle.is_fixed_point());
+    }
+
+    #[test]
+    fn max_generations() {
+        let iteration = iterate(2, square, Some(5));
+        assert_eq!(iteration.generation, 5);
+        assert_eq!(iteration.state, naive_state_at(5));
+        assert_eq!(iteration.cycle, None);
+    }
+
+    #[test]
+    fn fixed_point() {
+        let iteration = iterate(100, |x: &u64| x / 2, None);
+        assert_eq!(
+            iteration.cycle,
+            Some(Cycle {
+                start: 7,
+                period: 1
+            })
+        );
+        assert!(iteration.cycle.unwrap().is_fixed_point());
+        assert_eq!(iteration.state, 0);
+    }
+
+    #[test]
+    fn hash_collisions() {
+        // Distinct states all hashing alike must not be taken as a cycle.
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        struct Colliding(u64);
+
+        impl Hash for Colliding {
+            fn hash<H: std::hash::Hasher>(&self, _: &mut H) {}
+        }
+
+        let iteration = iterate(Colliding(0), |c| Colliding((c.0 + 1) % 5), None);
+        assert_eq!(
+            iteration.cycle,
+            Some(Cycle {
+                start: 0,
+                period: 5
+            })
+        );
+    }
+
+    #[test]
+    fn fast_forwarding() {
+        for generation in [0, 1, 11, 12, 13, 100] {
+            let mut nb_steps = 0;
+            let iteration = fast_forward(
+                2,
+                |x| {
+                    nb_steps += 1;
+                    square(x)
+                },
+                generation,
+            );
+            assert_eq!(iteration.generation, generation);
+            assert_eq!(iteration.state, naive_state_at(generation));
+            // Lead-in and cycle to find it, lead-in again to confirm it, then
+            // less than a cycle.
+            assert!(nb_steps <= 4 + 8 + 4 + 8);
+        }
+        let iteration = fast_forward(2, square, 1_000_000_000_000);
+        assert_eq!(iteration.state, naive_state_at(4 + 999_999_999_996 % 8));
+    }
+}
@@ -0,0 +1,336 @@
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read, Write};
+use std::ops::{Index, IndexMut};
+
+/// A kind of tile that can be read from and rendered to a text grid.
+pub trait Tile: Sized {
+    /// Returns tile represented by `c`, or `None` if `c` is not a valid tile.
+    fn from_char(c: char) -> Option<Self>;
+
+    /// Returns character representing tile.
+    fn to_char(&self) -> char;
+}
+
+/// Returns tiles of `line`, line number `line_number` of a text grid, without
+/// its `\n` or `\r\n` end-of-line if any.
+pub fn parse_row<T: Tile>(line: &[u8], line_number: usize) -> anyhow::Result<Vec<T>> {
+    let row = line.strip_suffix(b"\n").unwrap_or(line);
+    let row = row.strip_suffix(b"\r").unwrap_or(row);
+    let row = std::str::from_utf8(row).map_err(|e| {
+        anyhow::anyhow!(
+            "line {}, column {}: invalid UTF-8",
+            line_number,
+            // Column of first invalid character, after valid ones.
+            String::from_utf8_lossy(&row[..e.valid_up_to()])
+                .chars()
+                .count()
+                + 1
+        )
+    })?;
+    row.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            T::from_char(c).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "line {}, column {}: unexpected tile: {:?}",
+                    line_number,
+                    i + 1,
+                    c
+                )
+            })
+        })
+        .collect()
+}
+
+/// A rectangular grid of tiles indexed by `(x, y)` with `(0, 0)` at top-left.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    tiles: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T: Tile> Grid<T> {
+    /// Loads grid made of lines of tiles, all of same length.  Lines may end
+    /// with `\n` or `\r\n` and the last one may have no end-of-line.
+    pub fn load<R: Read>(mut input: R) -> anyhow::Result<Self> {
+        let mut raw = Vec::new();
+        input.read_to_end(&mut raw)?;
+
+        let mut tiles = Vec::with_capacity(raw.len());
+        let mut width = None;
+        let mut height = 0;
+        for line in raw.split_inclusive(|b| *b == b'\n') {
+            let line_number = height + 1;
+            let row = parse_row(line, line_number)?;
+            let nb_tiles = row.len();
+            tiles.extend(row);
+            let width = *width.get_or_insert(nb_tiles);
+            if nb_tiles != width {
+                anyhow::bail!(
+                    "line {}: expected {} tiles, found {}",
+                    line_number,
+                    width,
+                    nb_tiles
+                );
+            }
+            height += 1;
+        }
+
+        Ok(Self {
+            tiles,
+            width: width.unwrap_or(0),
+            height,
+        })
+    }
+
+    /// Writes grid in format read by `load`.
+    pub fn save<W: Write>(&self, mut output: W) -> io::Result<()> {
+        write!(output, "{}", self)?;
+        output.flush()
+    }
+}
+
+impl<T: Tile> Display for Grid<T> {
+    /// Formats grid as lines of tiles, each ending with `\n`.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for row in self.rows() {
+            for tile in row {
+                write!(f, "{}", tile.to_char())?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T> Grid<T> {
+    /// Creates grid of given size filled with `tile`.
+    pub fn new(width: usize, height: usize, tile: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            tiles: vec![tile; width * height],
+            width,
+            height,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns tile at `(x, y)` or `None` if out of grid.
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        self.to_offset(x, y).map(|offset| &self.tiles[offset])
+    }
+
+    /// Returns tile at `(x, y)` or `None` if out of grid.
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        self.to_offset(x, y).map(|offset| &mut self.tiles[offset])
+    }
+
+    /// Replaces tile at `(x, y)`.  Panics if out of grid.
+    pub fn set(&mut self, x: usize, y: usize, tile: T) {
+        self[(x, y)] = tile;
+    }
+
+    /// Returns tiles of row `y` from left to right.
+    pub fn row(&self, y: usize) -> &[T] {
+        assert!(y < self.height, "row {} out of grid", y);
+        &self.tiles[y * self.width..(y + 1) * self.width]
+    }
+
+    /// Returns iterator over rows from top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(|y| self.row(y))
+    }
+
+    /// Returns iterator over tiles of column `x` from top to bottom.
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "column {} out of grid", x);
+        self.tiles.iter().skip(x).step_by(self.width)
+    }
+
+    /// Returns iterator over columns from left to right.
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    /// Returns iterator over bands of `nb_rows` consecutive rows from top to
+    /// bottom, the last one possibly shorter, each as a row-major slice.
+    pub fn row_bands_mut(&mut self, nb_rows: usize) -> impl Iterator<Item = &mut [T]> {
+        assert!(nb_rows > 0, "empty bands");
+        self.tiles.chunks_mut((self.width * nb_rows).max(1))
+    }
+
+    /// Returns iterator over coordinates of all tiles in row-major order.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> + use<T> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    fn to_offset(&self, x: usize, y: usize) -> Option<usize> {
+        (x < self.width && y < self.height).then_some(y * self.width + x)
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        self.get(x, y)
+            .unwrap_or_else(|| panic!("({}, {}) out of grid", x, y))
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        self.get_mut(x, y)
+            .unwrap_or_else(|| panic!("({}, {}) out of grid", x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use std::io::Cursor;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Digit(u8);
+
+    impl Tile for Digit {
+        fn from_char(c: char) -> Option<Self> {
+            c.to_digit(10).map(|d| Digit(d as u8))
+        }
+
+        fn to_char(&self) -> char {
+            char::from_digit(self.0 as u32, 10).unwrap()
+        }
+    }
+
+    fn load(payload: &[u8]) -> anyhow::Result<Grid<Digit>> {
+        Grid::load(Cursor::new(payload))
+    }
+
+    fn digits<'a>(tiles: impl IntoIterator<Item = &'a Digit>) -> Vec<u8> {
+        tiles.into_iter().map(|d| d.0).collect()
+    }
+
+    #[test]
+    fn load_valid_grid() -> anyhow::Result<()> {
+        let grid = load(indoc! {br"
+            123
+            456
+        "})?;
+        assert_eq!(grid.width(), 3);
+        assert_eq!(grid.height(), 2);
+        assert_eq!(grid.get(0, 0), Some(&Digit(1)));
+        assert_eq!(grid.get(2, 1), Some(&Digit(6)));
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.get(0, 2), None);
+        Ok(())
+    }
+
+    #[test]
+    fn load_empty_grid() -> anyhow::Result<()> {
+        let grid = load(b"")?;
+        assert_eq!(grid.width(), 0);
+        assert_eq!(grid.height(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn load_without_final_end_of_line() -> anyhow::Result<()> {
+        let grid = load(b"12\n34")?;
+        assert_eq!(grid.height(), 2);
+        assert_eq!(digits(grid.row(1)), vec![3, 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn load_windows_text_file() -> anyhow::Result<()> {
+        let grid = load(b"12\r\n34\r\n")?;
+        assert_eq!(grid.width(), 2);
+        assert_eq!(grid.height(), 2);
+        assert_eq!(digits(grid.row(1)), vec![3, 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn reject_ragged_rows() {
+        let e = load(b"123\n45\n").unwrap_err();
+        assert_eq!(e.to_string(), "line 2: expected 3 tiles, found 2");
+        assert!(load(b"123\n\n").is_err());
+    }
+
+    #[test]
+    fn reject_unknown_tiles() {
+        let e = load(b"123\n4x6\n").unwrap_err();
+        assert_eq!(e.to_string(), "line 2, column 2: unexpected tile: 'x'");
+        let e = load("123\n4\u{e9}6\n".as_bytes()).unwrap_err();
+        assert_eq!(e.to_string(), "line 2, column 2: unexpected tile: '\u{e9}'");
+        let e = load(b"123\n45\xff\n").unwrap_err();
+        assert_eq!(e.to_string(), "line 2, column 3: invalid UTF-8");
+    }
+
+    #[test]
+    fn save_round_trip() -> anyhow::Result<()> {
+        let text = "123\n456\n";
+        let grid = load(text.as_bytes())?;
+        assert_eq!(grid.to_string(), text);
+        let mut saved = Vec::new();
+        grid.save(&mut saved)?;
+        assert_eq!(load(&saved)?, grid);
+        assert_eq!(load(b"12\r\n34")?.to_string(), "12\n34\n");
+        assert_eq!(load(b"")?.to_string(), "");
+        Ok(())
+    }
+
+    #[test]
+    fn accessors() {
+        let mut grid = Grid::new(2, 2, Digit(0));
+        grid.set(1, 0, Digit(1));
+        *grid.get_mut(0, 1).unwrap() = Digit(2);
+        grid[(1, 1)] = Digit(3);
+        assert_eq!(digits(grid.rows().flatten()), vec![0, 1, 2, 3]);
+        assert!(grid.get_mut(2, 0).is_none());
+    }
+
+    #[test]
+    fn rows_and_columns() -> anyhow::Result<()> {
+        let grid = load(b"123\n456\n")?;
+        let rows: Vec<Vec<u8>> = grid.rows().map(digits).collect();
+        assert_eq!(rows, vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        let columns: Vec<Vec<u8>> = grid.columns().map(digits).collect();
+        assert_eq!(columns, vec![vec![1, 4], vec![2, 5], vec![3, 6]]);
+        Ok(())
+    }
+
+    #[test]
+    fn row_bands() -> anyhow::Result<()> {
+        let mut grid = load(b"12\n34\n56\n")?;
+        let bands: Vec<Vec<u8>> = grid.row_bands_mut(2).map(|band| digits(&*band)).collect();
+        assert_eq!(bands, vec![vec![1, 2, 3, 4], vec![5, 6]]);
+        grid.row_bands_mut(2).last().unwrap()[1] = Digit(0);
+        assert_eq!(grid[(1, 2)], Digit(0));
+        assert_eq!(
+            Grid::<Digit>::new(0, 0, Digit(0)).row_bands_mut(1).count(),
+            0
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn positions() {
+        let grid = Grid::new(2, 2, Digit(0));
+        let positions: Vec<_> = grid.positions().collect();
+        assert_eq!(positions, vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+    }
+}
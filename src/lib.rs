@@ -1,14 +1,123 @@
 use std::env::args;
+use std::fmt::Display;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::str::FromStr;
+
+pub mod automaton;
+pub mod checkpoint;
+pub mod components;
+pub mod cycle;
+pub mod grid;
+pub mod neighborhood;
 
 /// Opens file given on first command-line argument.
 pub fn open_input_file() -> anyhow::Result<impl BufRead> {
-    let path = args()
-        .nth(1)
-        .ok_or_else(|| anyhow::anyhow!("missing input file"))?;
-    let reader = BufReader::new(
-        File::open(&path).map_err(|e| anyhow::anyhow!("failed to open {}: {}", path, e))?,
-    );
-    Ok(reader)
+    CommandLine::from_env().open_input_file()
+}
+
+/// Command-line arguments split into positional arguments and options.
+///
+/// Options are of the form `--name=value` or `--name` for boolean flags.
+/// Everything else is a positional argument.
+#[derive(Debug, Default)]
+pub struct CommandLine {
+    positionals: Vec<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl CommandLine {
+    /// Parses arguments of current process, skipping program name.
+    pub fn from_env() -> Self {
+        Self::parse(args().skip(1))
+    }
+
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Self {
+        let mut cmd_line = Self::default();
+        for arg in args {
+            match arg.strip_prefix("--") {
+                Some(option) => match option.split_once('=') {
+                    Some((name, value)) => cmd_line
+                        .options
+                        .push((name.to_string(), Some(value.to_string()))),
+                    None => cmd_line.options.push((option.to_string(), None)),
+                },
+                None => cmd_line.positionals.push(arg),
+            }
+        }
+        cmd_line
+    }
+
+    /// Returns `i`th positional argument, if any.
+    pub fn positional(&self, i: usize) -> Option<&str> {
+        self.positionals.get(i).map(String::as_str)
+    }
+
+    /// Returns value of last `--name=value` option parsed as `T`, or `None` if
+    /// option is missing.
+    pub fn option<T>(&self, name: &str) -> anyhow::Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match self.options.iter().rev().find(|(n, _)| n == name) {
+            Some((_, Some(value))) => value
+                .parse()
+                .map(Some)
+                .map_err(|e| anyhow::anyhow!("invalid value for --{}: {}: {}", name, value, e)),
+            Some((_, None)) => anyhow::bail!("missing value for --{}", name),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns true if `--name` flag is present.
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(n, v)| n == name && v.is_none())
+    }
+
+    /// Opens file given on first positional argument.
+    pub fn open_input_file(&self) -> anyhow::Result<impl BufRead + use<>> {
+        let path = self
+            .positional(0)
+            .ok_or_else(|| anyhow::anyhow!("missing input file"))?;
+        let reader = BufReader::new(
+            File::open(path).map_err(|e| anyhow::anyhow!("failed to open {}: {}", path, e))?,
+        );
+        Ok(reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> CommandLine {
+        CommandLine::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn positionals() {
+        let cmd_line = parse(&["a", "--x=1", "b"]);
+        assert_eq!(cmd_line.positional(0), Some("a"));
+        assert_eq!(cmd_line.positional(1), Some("b"));
+        assert_eq!(cmd_line.positional(2), None);
+    }
+
+    #[test]
+    fn options() -> anyhow::Result<()> {
+        let cmd_line = parse(&["--x=1", "--y", "--x=2"]);
+        assert_eq!(cmd_line.option::<usize>("x")?, Some(2));
+        assert_eq!(cmd_line.option::<usize>("z")?, None);
+        assert!(cmd_line.option::<usize>("y").is_err());
+        assert!(parse(&["--x=a"]).option::<usize>("x").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn flags() {
+        let cmd_line = parse(&["--x=1", "--y"]);
+        assert!(cmd_line.flag("y"));
+        assert!(!cmd_line.flag("x"));
+        assert!(!cmd_line.flag("z"));
+    }
 }
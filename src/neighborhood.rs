@@ -0,0 +1,373 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use crate::grid::{Grid, Tile};
+
+/// Largest radius of `Neighborhood::radius`, whose offsets then take under a
+/// megabyte.
+pub const MAX_RADIUS: usize = 100;
+
+/// Relative offsets of tiles considered neighbors of a tile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Neighborhood {
+    offsets: Vec<(isize, isize)>,
+}
+
+impl Neighborhood {
+    /// Returns the four orthogonally adjacent tiles.
+    pub fn von_neumann() -> Self {
+        Self::custom(vec![(0, -1), (-1, 0), (1, 0), (0, 1)])
+    }
+
+    /// Returns the eight tiles surrounding a tile.
+    pub fn moore() -> Self {
+        Self::radius(1)
+    }
+
+    /// Returns tiles at most `r` tiles away horizontally and vertically.
+    /// Panics if `r` is above `MAX_RADIUS`.
+    pub fn radius(r: usize) -> Self {
+        assert!(r <= MAX_RADIUS, "radius {} above {}", r, MAX_RADIUS);
+        let r = r as isize;
+        let offsets = (-r..=r)
+            .flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)))
+            .filter(|&offset| offset != (0, 0))
+            .collect();
+        Self::custom(offsets)
+    }
+
+    /// Returns the six adjacent tiles of a hexagonal tiling stored in axial
+    /// coordinates, where each row is shifted half a tile left of the row
+    /// below.
+    pub fn hexagonal() -> Self {
+        Self::custom(vec![(0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1)])
+    }
+
+    /// Returns neighborhood made of arbitrary offsets.
+    pub fn custom(offsets: Vec<(isize, isize)>) -> Self {
+        Self { offsets }
+    }
+
+    pub fn offsets(&self) -> &[(isize, isize)] {
+        &self.offsets
+    }
+
+    /// Returns neighborhood made of opposite offsets, so that a tile is in the
+    /// reversed neighborhood of another if and only if the latter is in the
+    /// neighborhood of the former.
+    pub fn reversed(&self) -> Self {
+        Self::custom(self.offsets.iter().map(|&(dx, dy)| (-dx, -dy)).collect())
+    }
+}
+
+impl Default for Neighborhood {
+    fn default() -> Self {
+        Self::moore()
+    }
+}
+
+impl FromStr for Neighborhood {
+    type Err = anyhow::Error;
+
+    /// Parses `von-neumann`, `moore`, `hex`, `radius:<r>` or
+    /// `custom:<dx>,<dy>;<dx>,<dy>;...`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "von-neumann" => return Ok(Self::von_neumann()),
+            "moore" => return Ok(Self::moore()),
+            "hex" => return Ok(Self::hexagonal()),
+            _ => {}
+        }
+        if let Some(r) = s.strip_prefix("radius:") {
+            let r = r
+                .parse()
+                .map_err(|e| anyhow::anyhow!("invalid radius: {}: {}", r, e))?;
+            if r > MAX_RADIUS {
+                anyhow::bail!("radius too large: {}: must be at most {}", r, MAX_RADIUS);
+            }
+            return Ok(Self::radius(r));
+        }
+        if let Some(offsets) = s.strip_prefix("custom:") {
+            let offsets = offsets
+                .split(';')
+                .map(|offset| {
+                    let parsed = offset
+                        .split_once(',')
+                        .and_then(|(dx, dy)| Some((dx.parse().ok()?, dy.parse().ok()?)));
+                    parsed.ok_or_else(|| anyhow::anyhow!("invalid offset: {}", offset))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            // Either would silently change neighbor counts.
+            let mut seen = HashSet::new();
+            for &offset in &offsets {
+                if offset == (0, 0) {
+                    anyhow::bail!("invalid offset: 0,0: a tile is not its own neighbor");
+                }
+                if !seen.insert(offset) {
+                    anyhow::bail!("duplicate offset: {},{}", offset.0, offset.1);
+                }
+            }
+            return Ok(Self::custom(offsets));
+        }
+        anyhow::bail!(
+            "unknown neighborhood: {} (expected von-neumann, moore, hex, radius:<r> or custom:<offsets>)",
+            s
+        )
+    }
+}
+
+/// How to treat neighbors falling outside the grid.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Boundary<T> {
+    /// Ignore them.
+    #[default]
+    Clip,
+    /// Take them from the opposite side of the grid.
+    Wrap,
+    /// Pretend they are all the given tile.
+    Pad(T),
+}
+
+impl<T: Tile> FromStr for Boundary<T> {
+    type Err = anyhow::Error;
+
+    /// Parses `clip`, `wrap` or `pad:<tile>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clip" => return Ok(Self::Clip),
+            "wrap" => return Ok(Self::Wrap),
+            _ => {}
+        }
+        if let Some(tile) = s.strip_prefix("pad:") {
+            let mut chars = tile.chars();
+            if let (Some(c), None) = (chars.next(), chars.next())
+                && let Some(tile) = T::from_char(c)
+            {
+                return Ok(Self::Pad(tile));
+            }
+            anyhow::bail!("invalid padding tile: {}", tile);
+        }
+        anyhow::bail!(
+            "unknown boundary: {} (expected clip, wrap or pad:<tile>)",
+            s
+        )
+    }
+}
+
+impl<T> Grid<T> {
+    /// Returns iterator over tiles in `neighborhood` of `(x, y)`, handling
+    /// those out of grid as specified by `boundary`.
+    pub fn neighborhood<'a>(
+        &'a self,
+        x: usize,
+        y: usize,
+        neighborhood: &'a Neighborhood,
+        boundary: &'a Boundary<T>,
+    ) -> impl Iterator<Item = &'a T> {
+        neighborhood.offsets().iter().filter_map(move |&offset| {
+            match self.resolve(x, y, offset, boundary) {
+                Some(p) => Some(&self[p]),
+                None => match boundary {
+                    Boundary::Pad(tile) => Some(tile),
+                    _ => None,
+                },
+            }
+        })
+    }
+
+    /// Returns iterator over coordinates of tiles in `neighborhood` of
+    /// `(x, y)` that are in grid, once wrapped around if `boundary` says so.
+    /// Padding tiles are skipped.
+    pub fn neighborhood_positions<'a>(
+        &'a self,
+        x: usize,
+        y: usize,
+        neighborhood: &'a Neighborhood,
+        boundary: &'a Boundary<T>,
+    ) -> impl Iterator<Item = (usize, usize)> {
+        neighborhood
+            .offsets()
+            .iter()
+            .filter_map(move |&offset| self.resolve(x, y, offset, boundary))
+    }
+
+    /// Returns coordinates of tile at `offset` from `(x, y)`, or `None` if out
+    /// of grid.
+    fn resolve(
+        &self,
+        x: usize,
+        y: usize,
+        (dx, dy): (isize, isize),
+        boundary: &Boundary<T>,
+    ) -> Option<(usize, usize)> {
+        let nx = x as isize + dx;
+        let ny = y as isize + dy;
+        let (width, height) = (self.width() as isize, self.height() as isize);
+        if (0..width).contains(&nx) && (0..height).contains(&ny) {
+            Some((nx as usize, ny as usize))
+        } else if matches!(boundary, Boundary::Wrap) {
+            Some((
+                nx.rem_euclid(width) as usize,
+                ny.rem_euclid(height) as usize,
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use std::io::Cursor;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Digit(u8);
+
+    impl Tile for Digit {
+        fn from_char(c: char) -> Option<Self> {
+            c.to_digit(10).map(|d| Digit(d as u8))
+        }
+
+        fn to_char(&self) -> char {
+            char::from_digit(self.0 as u32, 10).unwrap()
+        }
+    }
+
+    fn grid() -> Grid<Digit> {
+        Grid::load(Cursor::new(indoc! {br"
+            123
+            456
+            789
+        "}))
+        .unwrap()
+    }
+
+    fn neighbors(x: usize, y: usize, n: &Neighborhood, b: &Boundary<Digit>) -> Vec<u8> {
+        let grid = grid();
+        let mut digits: Vec<u8> = grid.neighborhood(x, y, n, b).map(|d| d.0).collect();
+        digits.sort();
+        digits
+    }
+
+    #[test]
+    fn von_neumann() {
+        let n = Neighborhood::von_neumann();
+        assert_eq!(neighbors(1, 1, &n, &Boundary::Clip), vec![2, 4, 6, 8]);
+        assert_eq!(neighbors(0, 0, &n, &Boundary::Clip), vec![2, 4]);
+    }
+
+    #[test]
+    fn moore() {
+        let n = Neighborhood::moore();
+        assert_eq!(
+            neighbors(1, 1, &n, &Boundary::Clip),
+            vec![1, 2, 3, 4, 6, 7, 8, 9]
+        );
+        assert_eq!(neighbors(2, 2, &n, &Boundary::Clip), vec![5, 6, 8]);
+        assert_eq!(neighbors(0, 0, &n, &Boundary::Clip), vec![2, 4, 5]);
+        let grid = grid();
+        let positions: Vec<_> = grid
+            .neighborhood_positions(2, 2, &n, &Boundary::Clip)
+            .collect();
+        assert_eq!(positions, vec![(1, 1), (2, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn radius() {
+        assert_eq!(Neighborhood::radius(0).offsets().len(), 0);
+        assert_eq!(Neighborhood::radius(2).offsets().len(), 24);
+        let n = Neighborhood::radius(2);
+        assert_eq!(
+            neighbors(0, 0, &n, &Boundary::Clip),
+            vec![2, 3, 4, 5, 6, 7, 8, 9]
+        );
+    }
+
+    #[test]
+    fn hexagonal() {
+        let n = Neighborhood::hexagonal();
+        assert_eq!(neighbors(1, 1, &n, &Boundary::Clip), vec![2, 3, 4, 6, 7, 8]);
+    }
+
+    #[test]
+    fn custom() {
+        let n = Neighborhood::custom(vec![(2, 0), (0, 2)]);
+        assert_eq!(neighbors(0, 0, &n, &Boundary::Clip), vec![3, 7]);
+        assert_eq!(neighbors(1, 1, &n, &Boundary::Clip), vec![]);
+    }
+
+    #[test]
+    fn wrap() {
+        let n = Neighborhood::von_neumann();
+        assert_eq!(neighbors(0, 0, &n, &Boundary::Wrap), vec![2, 3, 4, 7]);
+    }
+
+    #[test]
+    fn pad() {
+        let n = Neighborhood::von_neumann();
+        assert_eq!(
+            neighbors(0, 0, &n, &Boundary::Pad(Digit(0))),
+            vec![0, 0, 2, 4]
+        );
+    }
+
+    #[test]
+    fn positions() {
+        let grid = grid();
+        let n = Neighborhood::von_neumann();
+        let positions: Vec<_> = grid
+            .neighborhood_positions(0, 0, &n, &Boundary::Pad(Digit(0)))
+            .collect();
+        assert_eq!(positions, vec![(1, 0), (0, 1)]);
+        let positions: Vec<_> = grid
+            .neighborhood_positions(0, 0, &n, &Boundary::Wrap)
+            .collect();
+        assert_eq!(positions, vec![(0, 2), (2, 0), (1, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn reversed() {
+        let n = Neighborhood::custom(vec![(1, 0), (-2, 3)]);
+        assert_eq!(n.reversed(), Neighborhood::custom(vec![(-1, 0), (2, -3)]));
+    }
+
+    #[test]
+    fn parse_neighborhood() {
+        assert_eq!(
+            "moore".parse::<Neighborhood>().unwrap(),
+            Neighborhood::moore()
+        );
+        assert_eq!(
+            "hex".parse::<Neighborhood>().unwrap(),
+            Neighborhood::hexagonal()
+        );
+        assert_eq!(
+            "radius:3".parse::<Neighborhood>().unwrap(),
+            Neighborhood::radius(3)
+        );
+        assert_eq!(
+            "custom:1,0;-1,2".parse::<Neighborhood>().unwrap(),
+            Neighborhood::custom(vec![(1, 0), (-1, 2)])
+        );
+        assert!("custom:1".parse::<Neighborhood>().is_err());
+        assert!("custom:1,0;0,0".parse::<Neighborhood>().is_err());
+        assert!("custom:1,0;-1,2;1,0".parse::<Neighborhood>().is_err());
+        assert!("radius:100".parse::<Neighborhood>().is_ok());
+        assert!("radius:100000".parse::<Neighborhood>().is_err());
+        assert!("square".parse::<Neighborhood>().is_err());
+    }
+
+    #[test]
+    fn parse_boundary() {
+        assert_eq!("clip".parse::<Boundary<Digit>>().unwrap(), Boundary::Clip);
+        assert_eq!("wrap".parse::<Boundary<Digit>>().unwrap(), Boundary::Wrap);
+        assert_eq!(
+            "pad:7".parse::<Boundary<Digit>>().unwrap(),
+            Boundary::Pad(Digit(7))
+        );
+        assert!("pad:x".parse::<Boundary<Digit>>().is_err());
+        assert!("pad:12".parse::<Boundary<Digit>>().is_err());
+    }
+}